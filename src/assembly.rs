@@ -1,106 +1,238 @@
 use crate::data::{
-    BiOp, Compound, Declare, Expression, Function, Program, Statement, UnOp, Variable,
+    BiOp, Compound, Declarations, Declare, Expression, Function, Initializer, Program, Size,
+    Statement, Storage, UnOp, Variable,
 };
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Integer argument registers of the System V AMD64 calling convention, as
+/// their 64, 32, 16 and 8 bits names.
+const ARGUMENT_REGISTERS: [[&str; 4]; 6] = [
+    ["rdi", "edi", "di", "dil"],
+    ["rsi", "esi", "si", "sil"],
+    ["rdx", "edx", "dx", "dl"],
+    ["rcx", "ecx", "cx", "cl"],
+    ["r8", "r8d", "r8w", "r8b"],
+    ["r9", "r9d", "r9w", "r9b"],
+];
+
+/// Number of `xmm` registers passing floating arguments.
+const SSE_REGISTERS: usize = 8;
+
+const RAX: [&str; 4] = ["rax", "eax", "ax", "al"];
+const RCX: [&str; 4] = ["rcx", "ecx", "cx", "cl"];
+const RDX: [&str; 4] = ["rdx", "edx", "dx", "dl"];
 
 struct Assembly {
     asm: Vec<String>,
 }
 
+/// Where an argument is passed: in one of the integer registers, in the `xmm`
+/// register of this index, or on the stack in the slot of this index.
+enum Class {
+    Integer(&'static [&'static str; 4]),
+    Sse(usize),
+    Memory(usize),
+}
+
 pub struct Generator {
     count: u32,
     scope_manager: ScopeManager,
+    /// Number of 8 bytes slots pushed on the stack since the prologue, used to
+    /// keep `rsp` 16 bytes aligned on `call`.
+    depth: usize,
+    /// Functions with a body in this program; the others are resolved by the
+    /// linker through the PLT.
+    functions: HashSet<String>,
+    /// Return size of every declared function.
+    return_sizes: HashMap<String, Size>,
+    /// Parameter sizes of every declared function, the arguments being
+    /// converted to them.
+    parameter_sizes: HashMap<String, Vec<Size>>,
+    /// Return size of the function being generated.
+    return_size: Size,
+    /// Targets of `break` and `continue` for the enclosing loops.
+    break_labels: Vec<String>,
+    continue_labels: Vec<String>,
+    /// Distinct string literals of the program, emitted to `.rodata` under
+    /// the label `.LC<index>`.
+    strings: Vec<Vec<u8>>,
+    /// Jump tables of the dense switches, emitted to `.rodata` as the offset
+    /// of every target from the table.
+    jump_tables: Vec<(String, Vec<String>)>,
+    /// Labels of the cases and of the default of the enclosing switches.
+    switches: Vec<(HashMap<i64, String>, Option<String>)>,
+    /// Assembly labels of the labels of the function being generated.
+    labels: HashMap<String, String>,
+    /// Symbols with internal linkage, emitted without `.globl`.
+    local_symbols: HashSet<String>,
+    /// Data of the static locals, emitted after the globals.
+    statics: Assembly,
 }
 
 struct ScopeManager {
     scopes: Vec<Scope>,
     offset: i32,
+    /// Deepest offset reached by the locals of the current function.
+    stack_size: i32,
+    /// File-scope variables, addressed relative to `rip`.
+    globals: HashMap<String, Size>,
 }
 
 impl ScopeManager {
     fn new() -> ScopeManager {
         ScopeManager {
-            scopes: vec![Scope::new()],
+            scopes: vec![Scope::new(0)],
             offset: 0,
+            stack_size: 0,
+            globals: HashMap::new(),
         }
     }
 
     fn new_scope(&mut self) {
-        self.scopes.push(Scope::new());
+        self.scopes.push(Scope::new(self.offset));
     }
 
     fn drop(&mut self) {
-        self.scopes.pop();
-        self.offset = if let Some(scope) = self.scopes.last() {
-            if let Some((_, offset)) = scope.map.iter().max() {
-                *offset
-            } else {
-                0
-            }
-        } else {
-            0
+        if let Some(scope) = self.scopes.pop() {
+            self.offset = scope.offset;
         }
     }
 
     fn add_variable(&mut self, variable: &Variable) {
         if let Some(scope) = self.scopes.last_mut() {
-            let size_offset: i32 = variable.size.into();
+            let size = i32::from(&variable.size);
+            let alignment = variable.alignment();
+
+            self.offset = (self.offset - size).div_euclid(alignment) * alignment;
+            self.stack_size = self.stack_size.max(-self.offset);
+            scope.add_variable(variable, Address::Stack(self.offset));
+        } else {
+            panic!("no scope");
+        }
+    }
 
-            self.offset -= size_offset;
-            scope.add_variable(variable.name.clone(), self.offset);
+    /// Parameters passed on the stack live above the return address, at a
+    /// positive offset from `rbp`.
+    fn add_parameter(&mut self, variable: &Variable, offset: i32) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.add_variable(variable, Address::Stack(offset));
         } else {
             panic!("no scope");
         }
     }
 
-    fn get_offset(&self, variable: &String) -> &i32 {
+    /// Block-scope name of an object of static storage duration, a static
+    /// local or a variable declared `extern`, stored under `symbol`.
+    fn add_static(&mut self, variable: &Variable, symbol: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.add_variable(variable, Address::Symbol(symbol.to_string()));
+        } else {
+            panic!("no scope");
+        }
+    }
+
+    fn add_global(&mut self, variable: &Variable) {
+        self.globals
+            .insert(variable.name.clone(), variable.size.clone());
+    }
+
+    /// Whether the innermost scope declares the variable.
+    fn declares(&self, variable: &str) -> bool {
+        self.scopes.last().is_some_and(|scope| scope.get_variable(variable).is_ok())
+    }
+
+    fn get_local(&self, variable: &str) -> Option<&Address> {
+        let reverse_iterator = self.scopes.iter().rev();
+        for item in reverse_iterator {
+            if let Ok((address, _)) = item.get_variable(variable) {
+                return Some(address);
+            }
+        }
+        None
+    }
+
+    /// Symbol of an object of static storage.
+    fn get_symbol(&self, variable: &str) -> String {
+        match self.get_local(variable) {
+            Some(Address::Symbol(symbol)) => symbol.clone(),
+            Some(Address::Stack(_)) => panic!("`{}` is not of static storage", variable),
+            None if self.globals.contains_key(variable) => variable.to_string(),
+            None => panic!("`{}` undeclared", variable),
+        }
+    }
+
+    fn get_size(&self, variable: &str) -> Size {
+        match self.find_size(variable) {
+            Some(size) => size,
+            None => panic!("`{}` undeclared", variable),
+        }
+    }
+
+    fn find_size(&self, variable: &str) -> Option<Size> {
         let reverse_iterator = self.scopes.iter().rev();
         for item in reverse_iterator {
-            if let Ok(offset) = item.get_offset(variable) {
-                return offset;
+            if let Ok((_, size)) = item.get_variable(variable) {
+                return Some(size.clone());
             }
         }
-        panic!("no scope");
+        self.globals.get(variable).cloned()
     }
 
-    fn len(&self) -> usize {
-        let mut len = 0;
-        self.scopes.iter().for_each(|s| len += s.len());
-        len
+    /// Memory operand of a variable: locals shadow the globals.
+    fn get_address(&self, variable: &str) -> String {
+        if let Some(address) = self.get_local(variable) {
+            match address {
+                Address::Stack(offset) => format!("{}[rbp]", offset),
+                Address::Symbol(symbol) => format!("{}[rip]", symbol),
+            }
+        } else if self.globals.contains_key(variable) {
+            format!("{}[rip]", variable)
+        } else {
+            panic!("`{}` undeclared", variable);
+        }
     }
 }
 
+/// Where a block-scope variable is stored.
+#[derive(Debug)]
+enum Address {
+    /// Offset from `rbp`.
+    Stack(i32),
+    /// Symbol addressed relative to `rip`.
+    Symbol(String),
+}
+
 #[derive(Debug)]
 struct Scope {
-    map: HashMap<String, i32>,
+    map: HashMap<String, (Address, Size)>,
+    /// Offset of the enclosing scope, restored when this one is dropped.
+    offset: i32,
 }
 
 impl Scope {
-    fn new() -> Scope {
+    fn new(offset: i32) -> Scope {
         Scope {
             map: Default::default(),
+            offset,
         }
     }
 
-    fn add_variable(&mut self, name: String, offset: i32) {
-        if self.map.contains_key(&name) {
-            panic!("2 variable with same name : {}", name)
+    fn add_variable(&mut self, variable: &Variable, address: Address) {
+        if self.map.contains_key(&variable.name) {
+            panic!("2 variable with same name : {}", variable.name)
         }
-        self.map.insert(name, offset);
+        self.map
+            .insert(variable.name.clone(), (address, variable.size.clone()));
     }
 
-    fn get_offset(&self, variable: &String) -> Result<&i32, ()> {
+    fn get_variable(&self, variable: &str) -> Result<&(Address, Size), ()> {
         if let Some(offset) = self.map.get(variable) {
             Ok(offset)
         } else {
             Err(())
         }
     }
-
-    fn len(&self) -> usize {
-        self.map.len()
-    }
 }
 
 impl Assembly {
@@ -131,12 +263,337 @@ impl Generator {
         Generator {
             count: 0,
             scope_manager: ScopeManager::new(),
+            depth: 0,
+            functions: HashSet::new(),
+            return_sizes: HashMap::new(),
+            parameter_sizes: HashMap::new(),
+            return_size: Size::Int,
+            break_labels: vec![],
+            continue_labels: vec![],
+            strings: vec![],
+            jump_tables: vec![],
+            switches: vec![],
+            labels: HashMap::new(),
+            local_symbols: HashSet::new(),
+            statics: Assembly::new(),
         }
     }
 
+    /// Unique local label. The dot, which no C identifier contains, keeps
+    /// apart the labels of the program and the symbols of the C source.
     pub fn create_label(&mut self, label: &str) -> String {
         self.count += 1;
-        format!("_{}{}", label, self.count)
+        format!(".L{}.{}", label, self.count)
+    }
+
+    /// Whether the Intel syntax of the assembler reads the name as a register
+    /// or an operator, so that it cannot be written as a symbol.
+    pub fn is_reserved_symbol(name: &str) -> bool {
+        const NAMES: [&str; 24] = [
+            "rip", "eip", "cs", "ds", "es", "fs", "gs", "ss", "st", "flat", "offset", "and", "or",
+            "not", "xor", "mod", "shl", "shr", "eq", "ne", "lt", "le", "gt", "ge",
+        ];
+        // Register spelled with a number of the range, without leading zero
+        let numbered = |prefix: &str, numbers: std::ops::Range<u32>, suffixes: &[&str]| {
+            let rest = name.strip_prefix(prefix).unwrap_or_default();
+            suffixes
+                .iter()
+                .filter_map(|suffix| rest.strip_suffix(suffix))
+                .any(|digits| {
+                    !digits.is_empty()
+                        && digits.bytes().all(|c| c.is_ascii_digit())
+                        && (digits == "0" || !digits.starts_with('0'))
+                        && digits.parse().is_ok_and(|number| numbers.contains(&number))
+                })
+        };
+        let legacy = ["ax", "bx", "cx", "dx", "si", "di", "bp", "sp"];
+        let bytes = ["al", "ah", "bl", "bh", "cl", "ch", "dl", "dh", "sil", "dil", "bpl", "spl"];
+        NAMES.contains(&name)
+            || legacy.contains(&name.strip_prefix(['r', 'e']).unwrap_or(name))
+            || bytes.contains(&name)
+            || numbered("r", 8..16, &["", "d", "w", "b"])
+            || numbered("mm", 0..8, &[""])
+            || numbered("xmm", 0..32, &[""])
+            || numbered("ymm", 0..32, &[""])
+            || numbered("zmm", 0..32, &[""])
+            || numbered("k", 0..8, &[""])
+            || numbered("cr", 0..16, &[""])
+            || numbered("dr", 0..16, &[""])
+            || numbered("bnd", 0..4, &[""])
+            || numbered("tmm", 0..8, &[""])
+    }
+
+    /// Label of a string literal, identical literals sharing the same one.
+    fn string_label(&mut self, string: &[u8]) -> String {
+        let index = match self.strings.iter().position(|s| s.as_slice() == string) {
+            Some(index) => index,
+            None => {
+                self.strings.push(string.to_vec());
+                self.strings.len() - 1
+            }
+        };
+        format!(".LC{}", index)
+    }
+
+    /// Assembly label of a label of the current function, created on its
+    /// first use so that a `goto` may precede the label.
+    fn goto_label(&mut self, label: &str) -> String {
+        if let Some(label) = self.labels.get(label) {
+            return label.clone();
+        }
+        let created = self.create_label(label);
+        self.labels.insert(label.to_string(), created.clone());
+        created
+    }
+
+    fn push_register(&mut self, register: &str) -> String {
+        self.depth += 1;
+        format!("\tpush {}", register)
+    }
+
+    fn pop_register(&mut self, register: &str) -> String {
+        self.depth -= 1;
+        format!("\tpop {}", register)
+    }
+
+    fn size_directive(size: &Size) -> &'static str {
+        match size {
+            Size::Int | Size::UInt | Size::Float => "DWORD PTR",
+            Size::Byte | Size::UByte => "BYTE PTR",
+            Size::Short | Size::UShort => "WORD PTR",
+            Size::Long | Size::ULong | Size::Double | Size::Pointer(_, _) => "QWORD PTR",
+            Size::Array(_, _) | Size::Struct(_) => panic!("aggregates are not held in a register"),
+            Size::Void => panic!("void value not ignored as it ought to be"),
+        }
+    }
+
+    fn register(size: &Size, registers: &[&'static str; 4]) -> &'static str {
+        match size {
+            Size::Long
+            | Size::ULong
+            | Size::Double
+            | Size::Pointer(_, _)
+            | Size::Array(_, _)
+            | Size::Struct(_)
+            | Size::Void => registers[0],
+            Size::Int | Size::UInt | Size::Float => registers[1],
+            Size::Short | Size::UShort => registers[2],
+            Size::Byte | Size::UByte => registers[3],
+        }
+    }
+
+    /// Loads a value into `rax`, integers narrower than an int being promoted
+    /// and aggregates being held by their address.
+    fn load(size: &Size, address: &str) -> String {
+        match size {
+            Size::Array(_, _) | Size::Struct(_) => format!("\tlea rax, {}", address),
+            Size::Byte | Size::Short => {
+                format!("\tmovsx eax, {} {}", Self::size_directive(size), address)
+            }
+            Size::UByte | Size::UShort => {
+                format!("\tmovzx eax, {} {}", Self::size_directive(size), address)
+            }
+            Size::Float | Size::Double => format!(
+                "\tmov{} xmm0, {} {}",
+                Self::sse(size),
+                Self::size_directive(size),
+                address
+            ),
+            size => format!(
+                "\tmov {}, {} {}",
+                Self::register(size, &RAX),
+                Self::size_directive(size),
+                address
+            ),
+        }
+    }
+
+    /// Stores a value from one of the `registers`, or from `xmm0` for a
+    /// floating type.
+    fn store(size: &Size, address: &str, registers: &[&'static str; 4]) -> String {
+        if size.is_floating() {
+            return format!(
+                "\tmov{} {} {}, xmm0",
+                Self::sse(size),
+                Self::size_directive(size),
+                address
+            );
+        }
+        format!(
+            "\tmov {} {}, {}",
+            Self::size_directive(size),
+            address,
+            Self::register(size, registers)
+        )
+    }
+
+    /// Copies an aggregate from the address in `rax` to the one in `rcx`, and
+    /// leaves the destination in `rax`.
+    fn copy(size: &Size) -> Assembly {
+        let mut asm = Assembly::new();
+        let length = i32::from(size);
+        let mut offset = 0;
+        for (width, directive, register) in &[
+            (8, "QWORD PTR", "rdx"),
+            (4, "DWORD PTR", "edx"),
+            (2, "WORD PTR", "dx"),
+            (1, "BYTE PTR", "dl"),
+        ] {
+            while length - offset >= *width {
+                asm.push(format!("\tmov {}, {} [rax+{}]", register, directive, offset));
+                asm.push(format!("\tmov {} [rcx+{}], {}", directive, offset, register));
+                offset += width;
+            }
+        }
+        asm.push_str("\tmov rax, rcx");
+        asm
+    }
+
+    /// Zeroes an aggregate at the address in `rcx`.
+    fn zero(size: &Size) -> Assembly {
+        let mut asm = Assembly::new();
+        let length = i32::from(size);
+        let mut offset = 0;
+        for (width, directive) in &[
+            (8, "QWORD PTR"),
+            (4, "DWORD PTR"),
+            (2, "WORD PTR"),
+            (1, "BYTE PTR"),
+        ] {
+            while length - offset >= *width {
+                asm.push(format!("\tmov {} [rcx+{}], 0", directive, offset));
+                offset += width;
+            }
+        }
+        asm
+    }
+
+    /// Extends a value narrower than an int held in `eax`, with its sign
+    /// unless it is unsigned.
+    fn promote(size: &Size) -> Assembly {
+        let mut asm = Assembly::new();
+        match size {
+            Size::Byte => asm.push_str("\tmovsx eax, al"),
+            Size::UByte => asm.push_str("\tmovzx eax, al"),
+            Size::Short => asm.push_str("\tmovsx eax, ax"),
+            Size::UShort => asm.push_str("\tmovzx eax, ax"),
+            _ => {}
+        }
+        asm
+    }
+
+    /// Suffix of the SSE instructions on a floating type, for a scalar single
+    /// or double precision value.
+    fn sse(size: &Size) -> &'static str {
+        match size {
+            Size::Float => "ss",
+            _ => "sd",
+        }
+    }
+
+    /// Converts the scalar value held in `rax` or `xmm0` to another type. A
+    /// narrower integer is truncated and promoted again, a wider one is
+    /// extended from `eax` according to the signedness of the source; the
+    /// other integer conversions keep the bits. A floating value is truncated
+    /// toward zero. An unsigned long above `LONG_MAX` is converted through
+    /// its halved value, or through its difference with 2^63; the numeric
+    /// local labels keep these sequences apart from the other labels. A
+    /// value converted to void is discarded.
+    fn convert(from: &Size, to: &Size) -> Assembly {
+        let mut asm = Assembly::new();
+        if *to == Size::Void {
+            return asm;
+        }
+        match (from.is_floating(), to.is_floating()) {
+            (true, true) => {
+                if from != to {
+                    asm.push(format!("\tcvt{}2{} xmm0, xmm0", Self::sse(from), Self::sse(to)));
+                }
+                return asm;
+            }
+            (false, true) => {
+                let source = match from.clone().promote() {
+                    Size::Int => "eax",
+                    Size::UInt => {
+                        asm.push_str("\tmov eax, eax");
+                        "rax"
+                    }
+                    // The low bit is kept in the halved value for the rounding
+                    Size::ULong => {
+                        let sse = Self::sse(to);
+                        asm.push_str("\ttest rax, rax");
+                        asm.push_str("\tjs 1f");
+                        asm.push(format!("\tcvtsi2{} xmm0, rax", sse));
+                        asm.push_str("\tjmp 2f");
+                        asm.push_str("1:");
+                        asm.push_str("\tmov rcx, rax");
+                        asm.push_str("\tshr rcx, 1");
+                        asm.push_str("\tand eax, 1");
+                        asm.push_str("\tor rcx, rax");
+                        asm.push(format!("\tcvtsi2{} xmm0, rcx", sse));
+                        asm.push(format!("\tadd{} xmm0, xmm0", sse));
+                        asm.push_str("2:");
+                        return asm;
+                    }
+                    _ => "rax",
+                };
+                asm.push(format!("\tcvtsi2{} xmm0, {}", Self::sse(to), source));
+                return asm;
+            }
+            (true, false) if *to == Size::ULong => {
+                let sse = Self::sse(from);
+                let limit = match from {
+                    Size::Float => 2f32.powi(63).to_bits() as u64,
+                    _ => 2f64.powi(63).to_bits(),
+                };
+                asm.push(format!("\tmov rcx, {}", limit));
+                asm.push_str("\tmovq xmm1, rcx");
+                asm.push(format!("\tucomi{} xmm0, xmm1", sse));
+                asm.push_str("\tjae 1f");
+                asm.push(format!("\tcvtt{}2si rax, xmm0", sse));
+                asm.push_str("\tjmp 2f");
+                asm.push_str("1:");
+                asm.push(format!("\tsub{} xmm0, xmm1", sse));
+                asm.push(format!("\tcvtt{}2si rax, xmm0", sse));
+                asm.push_str("\tbtc rax, 63");
+                asm.push_str("2:");
+                return asm;
+            }
+            (true, false) => {
+                let destination = match to {
+                    Size::Int | Size::Short | Size::Byte => "eax",
+                    _ => "rax",
+                };
+                asm.push(format!("\tcvtt{}2si {}, xmm0", Self::sse(from), destination));
+                asm.push_asm(Self::promote(to));
+                return asm;
+            }
+            (false, false) => {}
+        }
+        match to {
+            Size::Byte | Size::UByte | Size::Short | Size::UShort if from != to => {
+                asm.push_asm(Self::promote(to))
+            }
+            Size::Long | Size::ULong | Size::Pointer(_, _) => match from {
+                Size::Int | Size::Short | Size::Byte => asm.push_str("\tmovsxd rax, eax"),
+                // The upper half may be left by a truncated long
+                Size::UInt => asm.push_str("\tmov eax, eax"),
+                _ => {}
+            },
+            _ => {}
+        }
+        asm
+    }
+}
+
+impl Declarations for Generator {
+    fn variable(&self, name: &str) -> Option<Variable> {
+        let size = self.scope_manager.find_size(name)?;
+        Some(Variable::new(name.to_string(), size))
+    }
+
+    fn function(&self, name: &str) -> Option<Size> {
+        self.return_sizes.get(name).cloned()
     }
 }
 
@@ -145,75 +602,295 @@ impl Generator {
         let mut asm = Assembly::new();
 
         asm.push_str("	.intel_syntax noprefix");
+        for Declare::Declare(storage, declarators) in &program.globals {
+            for (variable, _, _) in declarators {
+                self.scope_manager.add_global(variable);
+                if *storage == Some(Storage::Static) {
+                    self.local_symbols.insert(variable.name.clone());
+                }
+            }
+        }
+
         asm.push_str("	.text");
         for function in &program.functions {
-            asm.push_asm(self.generate_function(function));
+            if function.compounds.is_some() {
+                self.functions.insert(function.name.clone());
+            }
+            if function.storage == Some(Storage::Static) {
+                self.local_symbols.insert(function.name.clone());
+            }
+            self.return_sizes
+                .insert(function.name.clone(), function.size.clone());
+            self.parameter_sizes.insert(
+                function.name.clone(),
+                function.variables.iter().map(|v| v.size.clone()).collect(),
+            );
+        }
+        for function in &program.functions {
+            if let Some(compounds) = &function.compounds {
+                asm.push_asm(self.generate_function(function, compounds));
+            }
         }
+        // The checker has made the declarations of a global agree. It is
+        // defined once, by its initialized declaration if any; an `extern`
+        // declaration refers to a definition that may be elsewhere
+        let initialized: HashSet<&str> = program
+            .globals
+            .iter()
+            .flat_map(|Declare::Declare(_, declarators)| declarators)
+            .filter(|(_, initializer, _)| initializer.is_some())
+            .map(|(variable, _, _)| variable.name.as_str())
+            .collect();
+        let mut defined = HashSet::new();
+        for Declare::Declare(storage, declarators) in &program.globals {
+            for (variable, initializer, _) in declarators {
+                let definition = initializer.is_some()
+                    || (*storage != Some(Storage::Extern)
+                        && !initialized.contains(variable.name.as_str()));
+                if definition && defined.insert(variable.name.as_str()) {
+                    asm.push_asm(self.generate_global(variable, initializer));
+                }
+            }
+        }
+        asm.push_asm(std::mem::replace(&mut self.statics, Assembly::new()));
+        asm.push_asm(self.generate_rodata());
+        asm.push_str("	.section	.note.GNU-stack,\"\",@progbits");
         asm.concatenate()
     }
 
-    fn generate_function(&mut self, function: &Function) -> Assembly {
+    /// Initialized globals go to `.data`, the others are zero-filled in `.bss`.
+    /// The parser folds initializers into 64 bits constants, the bytes left
+    /// out by a brace list being zero.
+    fn generate_global(
+        &mut self,
+        variable: &Variable,
+        initializer: &Option<Initializer>,
+    ) -> Assembly {
         let mut asm = Assembly::new();
-        asm.push(format!("	.globl	{}", function.name));
-        asm.push(format!("	.type	{}, @function", function.name));
-        asm.push(format!("{}:", function.name));
-        asm.push_str("	push	rbp");
-        asm.push_str("	mov	rbp, rsp");
-        asm.push(format!(
-            "\tsub rsp, {}",
-            self.calculate_stack_size(&function.compounds) + 8
-        ));
+        let size = i32::from(&variable.size);
+        let alignment = variable.alignment();
 
-        self.scope_manager.new_scope();
-        for compound in &function.compounds {
-            asm.push_asm(self.generate_compound(compound));
-            asm.push_str("");
+        match initializer {
+            Some(Initializer::Expression(expression)) => {
+                asm.push_asm(self.generate_data(variable, size, alignment));
+                asm.push(self.data_directive(&variable.size, expression));
+            }
+            Some(Initializer::List(entries)) => {
+                asm.push_asm(self.generate_data(variable, size, alignment));
+                let mut entries: Vec<_> = entries.iter().collect();
+                entries.sort_by_key(|(offset, _, _)| *offset);
+                let mut cursor = 0;
+                for (offset, size, expression) in entries {
+                    if *offset < cursor {
+                        continue;
+                    }
+                    if *offset > cursor {
+                        asm.push(format!("	.zero	{}", offset - cursor));
+                    }
+                    asm.push(self.data_directive(size, expression));
+                    cursor = offset + i32::from(size);
+                }
+                if size > cursor {
+                    asm.push(format!("	.zero	{}", size - cursor));
+                }
+            }
+            None => {
+                asm.push_str("	.bss");
+                if !self.local_symbols.contains(&variable.name) {
+                    asm.push(format!("	.globl	{}", variable.name));
+                }
+                asm.push(format!("	.align	{}", alignment));
+                asm.push(format!("	.type	{}, @object", variable.name));
+                asm.push(format!("	.size	{}, {}", variable.name, size));
+                asm.push(format!("{}:", variable.name));
+                asm.push(format!("	.zero	{}", size));
+            }
         }
-
-        self.scope_manager.drop();
         asm
     }
 
-    fn calculate_stack_size(&self, compounds: &Vec<Compound>) -> i32 {
-        let mut stack_size = 0;
-        for compound in compounds {
-            match compound {
-                Compound::Declare(_) => {
-                    stack_size += 8;
-                }
-                Compound::Statement(Statement::ForDecl(_, _, _, vec)) => {
-                    if let Statement::Compound(v) = vec.borrow() {
-                        stack_size += self.calculate_stack_size(v);
+    /// Directive emitting a scalar of type `size` with a folded value, or
+    /// with an address constant for a pointer.
+    fn data_directive(&mut self, size: &Size, expression: &Expression) -> String {
+        match expression {
+            Expression::Double(value) => {
+                let (directive, bits) = match size {
+                    Size::Float => (".long", (*value as f32).to_bits() as u64),
+                    _ => (".quad", value.to_bits()),
+                };
+                format!("	{}	{}", directive, bits)
+            }
+            Expression::Long(value) => {
+                let directive = match size {
+                    Size::Int | Size::UInt => ".long",
+                    Size::Byte | Size::UByte => ".byte",
+                    Size::Short | Size::UShort => ".value",
+                    Size::Long | Size::ULong | Size::Pointer(_, _) => ".quad",
+                    Size::Float | Size::Double => panic!("integer initializer for a floating type"),
+                    Size::Array(_, _) | Size::Struct(_) => {
+                        panic!("invalid initializer for an aggregate")
                     }
-                    stack_size += 8;
+                    Size::Void => panic!("initializer for a void object"),
+                };
+                format!("	{}	{}", directive, size.truncate(*value))
+            }
+            expression if size.is_pointer() => match self.address_constant(expression) {
+                (symbol, 0) => format!("	.quad	{}", symbol),
+                (symbol, offset) => format!("	.quad	{}{:+}", symbol, offset),
+            },
+            expression => panic!("initializer is not a constant: {:?}", expression),
+        }
+    }
+
+    /// Symbol and byte offset of an address constant, as checked by the
+    /// parser.
+    fn address_constant(&mut self, expression: &Expression) -> (String, i64) {
+        match expression {
+            Expression::String(s) => (self.string_label(s), 0),
+            Expression::Variable(name) => (self.scope_manager.get_symbol(name), 0),
+            Expression::UnaryOperator(UnOp::AddressOf, lvalue) => match lvalue.as_ref() {
+                Expression::Variable(name) => (self.scope_manager.get_symbol(name), 0),
+                Expression::Member(e, name) => {
+                    let offset = match self.type_of(e) {
+                        Size::Struct(structure) => structure.member(name).offset,
+                        size => {
+                            panic!("request for member `{}` in a non struct ({:?})", name, size)
+                        }
+                    };
+                    let address = Expression::UnaryOperator(UnOp::AddressOf, e.clone());
+                    let (symbol, base) = self.address_constant(&address);
+                    (symbol, base + offset as i64)
                 }
-                Compound::Statement(Statement::For(_, _, _, vec)) => {
-                    if let Statement::Compound(v) = vec.borrow() {
-                        stack_size += self.calculate_stack_size(v);
-                    }
+                Expression::UnaryOperator(UnOp::Dereference, e) => self.address_constant(e),
+                lvalue => panic!("not an address constant: {:?}", lvalue),
+            },
+            Expression::BinaryOperator(e1, op, e2) => {
+                let (pointer, integer) = match e2.evaluate() {
+                    Some(integer) => (e1, integer),
+                    None => (e2, e1.evaluate().expect("integer constant")),
+                };
+                let scale = match self.type_of(pointer).decay() {
+                    Size::Pointer(pointed, _) => i32::from(pointed.as_ref()) as i64,
+                    size => panic!("not an address constant: {:?}", size),
+                };
+                let (symbol, offset) = self.address_constant(pointer);
+                match op {
+                    BiOp::Minus => (symbol, offset - integer * scale),
+                    _ => (symbol, offset + integer * scale),
                 }
-                Compound::Statement(Statement::For(_, _, _, vec)) => {
-                    if let Statement::Compound(v) = vec.borrow() {
-                        stack_size += self.calculate_stack_size(v);
+            }
+            Expression::Cast(_, expression) => self.address_constant(expression),
+            expression => panic!("not an address constant: {:?}", expression),
+        }
+    }
+
+    /// Header of an initialized global in `.data`, up to its label.
+    fn generate_data(&self, variable: &Variable, size: i32, alignment: i32) -> Assembly {
+        let mut asm = Assembly::new();
+        asm.push_str("	.data");
+        if !self.local_symbols.contains(&variable.name) {
+            asm.push(format!("	.globl	{}", variable.name));
+        }
+        asm.push(format!("	.align	{}", alignment));
+        asm.push(format!("	.type	{}, @object", variable.name));
+        asm.push(format!("	.size	{}, {}", variable.name, size));
+        asm.push(format!("{}:", variable.name));
+        asm
+    }
+
+    /// String literals and jump tables. `.string` adds the terminating null
+    /// byte; anything but printable ASCII is written as an octal escape.
+    fn generate_rodata(&self) -> Assembly {
+        let mut asm = Assembly::new();
+        if self.strings.is_empty() && self.jump_tables.is_empty() {
+            return asm;
+        }
+        asm.push_str("	.section	.rodata");
+        for (index, string) in self.strings.iter().enumerate() {
+            let mut escaped = String::new();
+            for &c in string {
+                match c {
+                    b'"' | b'\\' => {
+                        escaped.push('\\');
+                        escaped.push(c as char);
                     }
+                    0x20..=0x7e => escaped.push(c as char),
+                    c => escaped.push_str(&format!("\\{:03o}", c)),
                 }
-                Compound::Statement(Statement::Do(vec, _)) => {
-                    stack_size += self.calculate_stack_size(vec);
+            }
+            asm.push(format!(".LC{}:", index));
+            asm.push(format!("	.string	\"{}\"", escaped));
+        }
+        for (table, targets) in &self.jump_tables {
+            asm.push_str("	.align	4");
+            asm.push(format!("{}:", table));
+            for target in targets {
+                asm.push(format!("	.long	{}-{}", target, table));
+            }
+        }
+        asm
+    }
+
+    fn generate_function(&mut self, function: &Function, compounds: &[Compound]) -> Assembly {
+        let mut asm = Assembly::new();
+        if !self.local_symbols.contains(&function.name) {
+            asm.push(format!("	.globl	{}", function.name));
+        }
+        asm.push(format!("	.type	{}, @function", function.name));
+        asm.push(format!("{}:", function.name));
+        asm.push_str("	push	rbp");
+        asm.push_str("	mov	rbp, rsp");
+
+        let mut body = Assembly::new();
+        self.return_size = function.size.clone();
+        self.labels.clear();
+        self.scope_manager.stack_size = 0;
+        self.scope_manager.new_scope();
+        let sizes: Vec<Size> = function.variables.iter().map(|v| v.size.clone()).collect();
+        for (variable, class) in function.variables.iter().zip(Self::classify(&sizes)) {
+            match class {
+                Class::Integer(registers) => {
+                    self.scope_manager.add_variable(variable);
+                    body.push(Self::store(
+                        &variable.size,
+                        &self.scope_manager.get_address(&variable.name),
+                        registers,
+                    ));
                 }
-                Compound::Statement(Statement::If(_, vec, else_)) => {
-                    if let Statement::Compound(v) = vec.borrow() {
-                        stack_size += self.calculate_stack_size(v);
-                    }
-                    if let Some(else_) = else_ {
-                        if let Statement::Compound(v) = vec.borrow() {
-                            stack_size += self.calculate_stack_size(v);
-                        }
-                    }
+                Class::Sse(index) => {
+                    self.scope_manager.add_variable(variable);
+                    body.push(format!(
+                        "\tmov{} {} {}, xmm{}",
+                        Self::sse(&variable.size),
+                        Self::size_directive(&variable.size),
+                        self.scope_manager.get_address(&variable.name),
+                        index
+                    ));
+                }
+                Class::Memory(slot) => {
+                    let offset = 16 + 8 * slot as i32;
+                    self.scope_manager.add_parameter(variable, offset);
                 }
-                _ => {}
             }
         }
-        stack_size
+
+        for compound in compounds {
+            body.push_asm(self.generate_compound(compound));
+            body.push_str("");
+        }
+        self.scope_manager.drop();
+
+        // The frame is known once the body is generated; rsp stays 16 bytes
+        // aligned after the prologue
+        let stack_size = self.scope_manager.stack_size;
+        asm.push(format!("\tsub rsp, {}", (stack_size + 15) / 16 * 16));
+        asm.push_asm(body);
+
+        // Falling off the end of a function returns 0
+        asm.push_str("\tmov eax, 0");
+        asm.push_str("\tleave");
+        asm.push_str("\tret");
+        asm
     }
 
     fn generate_compound(&mut self, compound: &Compound) -> Assembly {
@@ -232,20 +909,62 @@ impl Generator {
 
     fn generate_declaration(&mut self, declaration: &Declare) -> Assembly {
         let mut asm = Assembly::new();
-        if let Declare::Declare(variable, expression) = declaration {
-            self.scope_manager.add_variable(variable);
-            let from;
-            if let Some(expression) = expression {
-                asm.push_asm(self.generate_expression(expression));
-                from = "eax";
-            } else {
-                from = "0";
+        let Declare::Declare(storage, declarators) = declaration;
+        for (variable, initializer, _) in declarators {
+            match storage {
+                Some(Storage::Static) => {
+                    // The dot keeps the label apart from any C identifier
+                    self.count += 1;
+                    let symbol = format!("{}.{}", variable.name, self.count);
+                    self.scope_manager.add_static(variable, &symbol);
+                    self.local_symbols.insert(symbol.clone());
+                    let data = Variable::new(symbol, variable.size.clone());
+                    let data = self.generate_global(&data, initializer);
+                    self.statics.push_asm(data);
+                    continue;
+                }
+                Some(Storage::Extern) => {
+                    // The block may already declare the object
+                    if !self.scope_manager.declares(&variable.name) {
+                        self.scope_manager.add_static(variable, &variable.name);
+                    }
+                    continue;
+                }
+                None => self.scope_manager.add_variable(variable),
+            }
+            let address = self.scope_manager.get_address(&variable.name);
+            match initializer {
+                Some(Initializer::Expression(expression)) if variable.size.is_aggregate() => {
+                    asm.push_asm(self.generate_expression(expression));
+                    asm.push(format!("\tlea rcx, {}", address));
+                    asm.push_asm(Self::copy(&variable.size));
+                }
+                Some(Initializer::Expression(expression)) => {
+                    asm.push_asm(self.generate_converted(expression, &variable.size));
+                    asm.push(Self::store(&variable.size, &address, &RAX));
+                }
+                Some(Initializer::List(entries)) => {
+                    // The object is zeroed, then every initialized scalar is stored
+                    asm.push(format!("\tlea rcx, {}", address));
+                    asm.push_asm(Self::zero(&variable.size));
+                    let base = match self.scope_manager.get_local(&variable.name) {
+                        Some(Address::Stack(offset)) => *offset,
+                        _ => panic!("`{}` is not on the stack", variable.name),
+                    };
+                    for (offset, size, expression) in entries {
+                        asm.push_asm(self.generate_converted(expression, size));
+                        asm.push(Self::store(size, &format!("{}[rbp]", base + offset), &RAX));
+                    }
+                }
+                None if !variable.size.is_aggregate() => {
+                    asm.push(format!(
+                        "\tmov {} {}, 0",
+                        Self::size_directive(&variable.size),
+                        address
+                    ));
+                }
+                None => {}
             }
-            asm.push(format!(
-                "\tmov DWORD PTR {}[rbp], {}",
-                self.scope_manager.get_offset(&variable.name),
-                from
-            ))
         }
         asm
     }
@@ -254,22 +973,24 @@ impl Generator {
         let mut asm = Assembly::new();
 
         match statement {
-            Statement::Return(expr) => {
-                asm.push_asm(self.generate_expression(expr));
+            Statement::Return(expr, _) => {
+                if let Some(expr) = expr {
+                    let size = self.return_size.clone();
+                    asm.push_asm(self.generate_converted(expr, &size));
+                }
                 asm.push_str("\tleave");
                 asm.push_str("\tret");
             }
-            Statement::Expression(expression) => {
+            Statement::Expression(expression, _) => {
                 if let Some(expression) = expression {
                     asm.push_asm(self.generate_expression(expression));
                 }
             }
-            Statement::If(cond, body, else_statement) => {
+            Statement::If(cond, body, else_statement, _) => {
                 let post_conditional = self.create_label("post_conditional");
                 let else_conditional = self.create_label("else_conditional");
 
-                asm.push_asm(self.generate_expression(cond));
-                asm.push_str("\tcmp eax, 0");
+                asm.push_asm(self.generate_condition(cond));
                 asm.push(format!(
                     "\tje {}",
                     if else_statement.is_some() {
@@ -297,85 +1018,457 @@ impl Generator {
                 }
                 self.scope_manager.drop();
             }
-            Statement::For(initial, condition, post_expression, body) => {
-                let loop_ = self.create_label("loop");
-                let cond = self.create_label("condition");
-
+            Statement::For(initial, condition, post_expression, body, _) => {
                 if let Some(initial) = initial {
                     asm.push_asm(self.generate_expression(initial));
                 }
-                asm.push(format!("\tjmp {}", cond));
-                asm.push(format!("{}:", loop_));
-                asm.push_asm(self.generate_statement(body));
-
-                if let Some(post_expression) = post_expression {
-                    asm.push_asm(self.generate_expression(post_expression));
-                }
-
-                asm.push(format!("{}:", cond));
-
-                asm.push_asm(self.generate_expression(condition));
-                asm.push_str("\tcmp eax, 0");
-                asm.push(format!("\tjne {}", loop_));
+                asm.push_asm(self.generate_for(condition, post_expression, body));
             }
-            Statement::ForDecl(declare, condition, post_expression, body) => {
-                let loop_ = self.create_label("loop");
-                let cond = self.create_label("condition");
-
+            Statement::ForDecl(declare, condition, post_expression, body, _) => {
                 self.scope_manager.new_scope();
                 asm.push_asm(self.generate_declaration(declare));
-
-                asm.push(format!("\tjmp {}", cond));
-                asm.push(format!("{}:", loop_));
-                asm.push_asm(self.generate_statement(body));
-
-                if let Some(post_expression) = post_expression {
-                    asm.push_asm(self.generate_expression(post_expression));
-                }
-
-                asm.push(format!("{}:", cond));
-
-                asm.push_asm(self.generate_expression(condition));
-                asm.push_str("\tcmp eax, 0");
-                asm.push(format!("\tjne {}", loop_));
+                asm.push_asm(self.generate_for(condition, post_expression, body));
                 self.scope_manager.drop();
             }
-            Statement::While(condition, body) => {
+            Statement::While(condition, body, _) => {
                 let loop_ = self.create_label("loop");
                 let cond = self.create_label("condition");
+                let end = self.create_label("end_loop");
 
                 asm.push(format!("\tjmp {}", cond));
                 asm.push(format!("{}:", loop_));
+                self.break_labels.push(end.clone());
+                self.continue_labels.push(cond.clone());
                 asm.push_asm(self.generate_statement(body));
+                self.break_labels.pop();
+                self.continue_labels.pop();
 
                 asm.push(format!("{}:", cond));
 
-                asm.push_asm(self.generate_expression(condition));
-                asm.push_str("\tcmp eax, 0");
+                asm.push_asm(self.generate_condition(condition));
                 asm.push(format!("\tjne {}", loop_));
+                asm.push(format!("{}:", end));
             }
-            Statement::Do(body, condition) => {
+            Statement::Do(body, condition, _) => {
                 let loop_ = self.create_label("loop");
                 let cond = self.create_label("condition");
+                let end = self.create_label("end_loop");
 
-                asm.push(format!("\tjmp {}", cond));
                 asm.push(format!("{}:", loop_));
+                self.break_labels.push(end.clone());
+                self.continue_labels.push(cond.clone());
+                self.scope_manager.new_scope();
                 for compound in body {
                     asm.push_asm(self.generate_compound(compound));
                 }
+                self.scope_manager.drop();
+                self.break_labels.pop();
+                self.continue_labels.pop();
 
                 asm.push(format!("{}:", cond));
 
-                asm.push_asm(self.generate_expression(condition));
-                asm.push_str("\tcmp eax, 0");
+                asm.push_asm(self.generate_condition(condition));
                 asm.push(format!("\tjne {}", loop_));
+                asm.push(format!("{}:", end));
+            }
+            Statement::Switch(expression, body, _) => {
+                asm.push_asm(self.generate_switch(expression, body));
+            }
+            Statement::Case(value, body, _) => {
+                let (cases, _) = self.switches.last().expect("`case` outside of a switch");
+                asm.push(format!("{}:", cases[value]));
+                asm.push_asm(self.generate_statement(body));
+            }
+            Statement::Default(body, _) => {
+                let default = self.switches.last().and_then(|(_, default)| default.clone());
+                asm.push(format!("{}:", default.expect("`default` outside of a switch")));
+                asm.push_asm(self.generate_statement(body));
+            }
+            Statement::Goto(label, _) => {
+                let label = self.goto_label(label);
+                asm.push(format!("\tjmp {}", label));
+            }
+            Statement::Label(label, body, _) => {
+                let label = self.goto_label(label);
+                asm.push(format!("{}:", label));
+                asm.push_asm(self.generate_statement(body));
+            }
+            Statement::Break(_) => {
+                let label = self.break_labels.last().expect("`break` outside of a loop");
+                asm.push(format!("\tjmp {}", label));
+            }
+            Statement::Continue(_) => {
+                let label = self
+                    .continue_labels
+                    .last()
+                    .expect("`continue` outside of a loop");
+                asm.push(format!("\tjmp {}", label));
+            }
+        }
+        asm
+    }
+
+    /// Shared by `For` and `ForDecl` once the initial clause is generated:
+    /// `continue` jumps to the post expression, `break` past the condition.
+    fn generate_for(
+        &mut self,
+        condition: &Expression,
+        post_expression: &Option<Expression>,
+        body: &Statement,
+    ) -> Assembly {
+        let mut asm = Assembly::new();
+        let loop_ = self.create_label("loop");
+        let post = self.create_label("post_expression");
+        let cond = self.create_label("condition");
+        let end = self.create_label("end_loop");
+
+        asm.push(format!("\tjmp {}", cond));
+        asm.push(format!("{}:", loop_));
+        self.break_labels.push(end.clone());
+        self.continue_labels.push(post.clone());
+        asm.push_asm(self.generate_statement(body));
+        self.break_labels.pop();
+        self.continue_labels.pop();
+
+        asm.push(format!("{}:", post));
+        if let Some(post_expression) = post_expression {
+            asm.push_asm(self.generate_expression(post_expression));
+        }
+
+        asm.push(format!("{}:", cond));
+
+        asm.push_asm(self.generate_condition(condition));
+        asm.push(format!("\tjne {}", loop_));
+        asm.push(format!("{}:", end));
+        asm
+    }
+
+    /// Dispatches on the value of a switch through a jump table indexed by
+    /// the value minus the lowest case when the cases are dense, through a
+    /// compare chain otherwise. `break` jumps past the body.
+    fn generate_switch(&mut self, expression: &Expression, body: &Statement) -> Assembly {
+        let mut asm = Assembly::new();
+        let end = self.create_label("end_switch");
+
+        let mut values = vec![];
+        let mut has_default = false;
+        Self::collect_cases(body, &mut values, &mut has_default);
+        let cases: HashMap<i64, String> = values
+            .iter()
+            .map(|value| (*value, self.create_label("case")))
+            .collect();
+        let default = if has_default {
+            Some(self.create_label("default"))
+        } else {
+            None
+        };
+        let otherwise = default.clone().unwrap_or_else(|| end.clone());
+
+        // Case values are converted to the promoted type of the expression
+        let size = self.type_of(expression).promote();
+        let register = Self::register(&size, &RAX);
+        let mut converted: Vec<(i64, String)> = cases
+            .iter()
+            .map(|(value, label)| (size.truncate(*value), label.clone()))
+            .collect();
+        converted.sort_unstable();
+        asm.push_asm(self.generate_expression(expression));
+        match (converted.first(), converted.last()) {
+            // At least a third of the table are cases
+            (Some(&(low, _)), Some(&(high, _)))
+                if converted.len() >= 4
+                    && i128::from(high) - i128::from(low) < 3 * converted.len() as i128 =>
+            {
+                let table = self.create_label("switch_table");
+                let immediate = Self::case_immediate(&mut asm, &size, low);
+                asm.push(format!("\tsub {}, {}", register, immediate));
+                asm.push(format!("\tcmp {}, {}", register, high.wrapping_sub(low)));
+                asm.push(format!("\tja {}", otherwise));
+                asm.push(format!("\tlea rcx, {}[rip]", table));
+                asm.push_str("\tmovsxd rax, DWORD PTR [rcx+rax*4]");
+                asm.push_str("\tadd rax, rcx");
+                asm.push_str("\tjmp rax");
+                let labels: HashMap<i64, String> = converted.into_iter().collect();
+                let targets = (low..=high)
+                    .map(|value| labels.get(&value).unwrap_or(&otherwise).clone())
+                    .collect();
+                self.jump_tables.push((table, targets));
+            }
+            _ => {
+                for (value, label) in &converted {
+                    let immediate = Self::case_immediate(&mut asm, &size, *value);
+                    asm.push(format!("\tcmp {}, {}", register, immediate));
+                    asm.push(format!("\tje {}", label));
+                }
+                asm.push(format!("\tjmp {}", otherwise));
+            }
+        }
+
+        self.switches.push((cases, default));
+        self.break_labels.push(end.clone());
+        asm.push_asm(self.generate_statement(body));
+        self.break_labels.pop();
+        self.switches.pop();
+        asm.push(format!("{}:", end));
+        asm
+    }
+
+    /// Operand comparing a case value of a switch on `size`. The 64 bits
+    /// values not fitting an immediate are loaded in `rcx` first.
+    fn case_immediate(asm: &mut Assembly, size: &Size, value: i64) -> String {
+        if i32::from(size) < 8 {
+            (value as i32).to_string()
+        } else if (i64::from(i32::MIN)..=i64::from(i32::MAX)).contains(&value) {
+            value.to_string()
+        } else {
+            asm.push(format!("\tmov rcx, {}", value));
+            "rcx".to_string()
+        }
+    }
+
+    /// Case values of a switch body and whether it has a default label, the
+    /// nested switches having their own.
+    fn collect_cases(statement: &Statement, values: &mut Vec<i64>, default: &mut bool) {
+        match statement {
+            Statement::Case(value, body, _) => {
+                values.push(*value);
+                Self::collect_cases(body, values, default);
+            }
+            Statement::Default(body, _) => {
+                *default = true;
+                Self::collect_cases(body, values, default);
+            }
+            Statement::If(_, body, else_statement, _) => {
+                Self::collect_cases(body, values, default);
+                if let Some(else_statement) = else_statement {
+                    Self::collect_cases(else_statement, values, default);
+                }
+            }
+            Statement::Compound(compounds) | Statement::Do(compounds, _, _) => {
+                for compound in compounds {
+                    if let Compound::Statement(statement) = compound {
+                        Self::collect_cases(statement, values, default);
+                    }
+                }
+            }
+            Statement::For(_, _, _, body, _)
+            | Statement::ForDecl(_, _, _, body, _)
+            | Statement::While(_, body, _)
+            | Statement::Label(_, body, _) => Self::collect_cases(body, values, default),
+            _ => {}
+        }
+    }
+
+    /// Type of an expression, the program being already checked.
+    fn type_of(&self, expression: &Expression) -> Size {
+        match expression.type_of(self) {
+            Ok(size) => size,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Evaluates an expression and converts its value to `size`.
+    fn generate_converted(&mut self, expression: &Expression, size: &Size) -> Assembly {
+        let from = self.type_of(expression).decay();
+        let mut asm = self.generate_expression(expression);
+        asm.push_asm(Self::convert(&from, size));
+        asm
+    }
+
+    /// Evaluates a controlling expression and compares it to zero. A floating
+    /// value is first compared to zero by the SSE unit, NaN being true.
+    fn generate_condition(&mut self, expression: &Expression) -> Assembly {
+        let mut asm = self.generate_expression(expression);
+        let size = self.type_of(expression).decay().promote();
+        if size.is_floating() {
+            asm.push_str("\txorps xmm1, xmm1");
+            asm.push(format!("\tucomi{} xmm0, xmm1", Self::sse(&size)));
+            asm.push_str("\tmov eax, 0");
+            asm.push_str("\tsetne al");
+            asm.push_str("\tsetp cl");
+            asm.push_str("\tor al, cl");
+            asm.push_str("\tcmp eax, 0");
+        } else {
+            asm.push(format!("\tcmp {}, 0", Self::register(&size, &RAX)));
+        }
+        asm
+    }
+
+    /// Pushes the value held in `rax`, or in `xmm0` for a floating type.
+    fn push_value(&mut self, size: &Size) -> Assembly {
+        let mut asm = Assembly::new();
+        if size.is_floating() {
+            asm.push_str("\tmovq rax, xmm0");
+        }
+        let push = self.push_register("rax");
+        asm.push(push);
+        asm
+    }
+
+    /// Address of an lvalue into `rax`.
+    fn generate_address(&mut self, expression: &Expression) -> Assembly {
+        let mut asm = Assembly::new();
+        match expression {
+            Expression::Variable(v) => {
+                asm.push(format!("\tlea rax, {}", self.scope_manager.get_address(v)));
+            }
+            Expression::UnaryOperator(UnOp::Dereference, e) => {
+                asm.push_asm(self.generate_expression(e));
+            }
+            Expression::Member(e, name) => {
+                let offset = match self.type_of(e) {
+                    Size::Struct(structure) => structure.member(name).offset,
+                    size => panic!("request for member `{}` in a non struct ({:?})", name, size),
+                };
+                asm.push_asm(self.generate_address(e));
+                if offset != 0 {
+                    asm.push(format!("\tadd rax, {}", offset));
+                }
+            }
+            _ => panic!("lvalue required: {:?}", expression),
+        }
+        asm
+    }
+
+    /// Stores the value into the lvalue, locals and globals being addressed
+    /// directly, the others through the address held in `rcx`.
+    fn generate_assignment(&mut self, lvalue: &Expression, value: &Expression) -> Assembly {
+        let mut asm = Assembly::new();
+        let size = self.type_of(lvalue);
+
+        // The value of an assignment is the converted value
+        match lvalue {
+            Expression::Variable(v) if !size.is_aggregate() => {
+                asm.push_asm(self.generate_converted(value, &size));
+                asm.push(Self::store(&size, &self.scope_manager.get_address(v), &RAX));
+            }
+            _ => {
+                asm.push_asm(self.generate_address(lvalue));
+                let push = self.push_register("rax");
+                asm.push(push);
+                if size.is_aggregate() {
+                    asm.push_asm(self.generate_expression(value));
+                } else {
+                    asm.push_asm(self.generate_converted(value, &size));
+                }
+                let pop = self.pop_register("rcx");
+                asm.push(pop);
+                if size.is_aggregate() {
+                    asm.push_asm(Self::copy(&size));
+                } else {
+                    asm.push(Self::store(&size, "[rcx]", &RAX));
+                }
+            }
+        }
+        asm
+    }
+
+    /// Applies the operator to the lvalue and the value, and stores the result.
+    /// The value and the address of the lvalue are computed once and kept on
+    /// the stack, under the old value for a postfix operator which evaluates
+    /// to it. A pointer is moved by the value scaled by the pointed size.
+    fn generate_compound_assignment(
+        &mut self,
+        lvalue: &Expression,
+        op: &BiOp,
+        value: &Expression,
+        post: bool,
+    ) -> Assembly {
+        let mut asm = Assembly::new();
+        let size = self.type_of(lvalue);
+        let value_size = self.type_of(value).decay();
+        // Types of the operation and of its right operand
+        let (operation, operand) = match (&size, op) {
+            (Size::Pointer(_, _), _) => (Size::Long, Size::Long),
+            (_, BiOp::BitwiseShiftLeft) | (_, BiOp::BitwiseShiftRight) => {
+                (size.clone().promote(), value_size.promote())
             }
-            Statement::Break => {
+            _ => {
+                let common = size.clone().common(value_size);
+                (common.clone(), common)
+            }
+        };
+
+        asm.push_asm(self.generate_converted(value, &operand));
+        if let Size::Pointer(pointed, _) = &size {
+            asm.push(format!("\timul rax, rax, {}", i32::from(pointed.as_ref())));
+        }
+        asm.push_asm(self.push_value(&operand));
+        asm.push_asm(self.generate_address(lvalue));
+        let push = self.push_register("rax");
+        asm.push(push);
+        asm.push(Self::load(&size, "[rax]"));
+        if post {
+            asm.push_asm(self.push_value(&size));
+        }
+        asm.push_asm(Self::convert(&size, &operation));
+        asm.push(format!(
+            "\tmov rcx, QWORD PTR [rsp+{}]",
+            if post { 16 } else { 8 }
+        ));
+        asm.push_asm(self.generate_operator(op, &operation));
+        asm.push_asm(Self::convert(&operation, &size));
 
+        if post {
+            let pop = self.pop_register("rdx");
+            asm.push(pop);
+        }
+        let pop = self.pop_register("rcx");
+        asm.push(pop);
+        asm.push(Self::store(&size, "[rcx]", &RAX));
+        // The right operand is dropped
+        let pop = self.pop_register("rcx");
+        asm.push(pop);
+        if post {
+            asm.push_str("\tmov rax, rdx");
+            if size.is_floating() {
+                asm.push_str("\tmovq xmm0, rax");
             }
-            Statement::Continue => {
+        }
+        asm
+    }
+
+    /// `+` and `-` with a pointer operand, the integer being scaled by the size
+    /// of the pointed type; the difference of two pointers is divided by it.
+    fn generate_pointer_arithmetic(
+        &mut self,
+        e1: &Expression,
+        op: &BiOp,
+        e2: &Expression,
+    ) -> Assembly {
+        let mut asm = Assembly::new();
+        let (s1, s2) = (self.type_of(e1).decay(), self.type_of(e2).decay());
 
+        asm.push_asm(self.generate_expression(e1));
+        match &s2 {
+            Size::Pointer(pointed, _) if !s1.is_pointer() => {
+                asm.push_asm(Self::convert(&s1, &Size::Long));
+                asm.push(format!("\timul rax, rax, {}", i32::from(pointed.as_ref())));
             }
+            _ => {}
+        }
+        let push = self.push_register("rax");
+        asm.push(push);
+        asm.push_asm(self.generate_expression(e2));
+        match &s1 {
+            Size::Pointer(pointed, _) if !s2.is_pointer() => {
+                asm.push_asm(Self::convert(&s2, &Size::Long));
+                asm.push(format!("\timul rax, rax, {}", i32::from(pointed.as_ref())));
+            }
+            _ => {}
+        }
+        asm.push_str("\tmov rcx, rax");
+        let pop = self.pop_register("rax");
+        asm.push(pop);
+
+        match op {
+            BiOp::Addition => asm.push_str("\tadd rax, rcx"),
+            _ => asm.push_str("\tsub rax, rcx"),
+        }
+        if let (Size::Pointer(pointed, _), true) = (&s1, s2.is_pointer()) {
+            asm.push(format!("\tmov rcx, {}", i32::from(pointed.as_ref())));
+            asm.push_str("\tcqo");
+            asm.push_str("\tidiv rcx");
         }
         asm
     }
@@ -384,34 +1477,67 @@ impl Generator {
         let mut asm = Assembly::new();
         //println!("{:?}", expression);
         match expression {
-            Expression::Int(nu) => {
+            Expression::Int(nu) | Expression::Enumerator(_, nu) => {
                 asm.push(format!("\tmov eax, {}", nu));
             }
+            Expression::UInt(value) => {
+                asm.push(format!("\tmov eax, {}", value));
+            }
+            Expression::Long(value) => {
+                asm.push(format!("\tmov rax, {}", value));
+            }
+            Expression::ULong(value) => {
+                asm.push(format!("\tmov rax, {}", *value as i64));
+            }
+            // Floating constants are moved through `rax` as their bits
+            Expression::Float(value) => {
+                asm.push(format!("\tmov eax, {}", value.to_bits()));
+                asm.push_str("\tmovd xmm0, eax");
+            }
+            Expression::Double(value) => {
+                asm.push(format!("\tmov rax, {}", value.to_bits()));
+                asm.push_str("\tmovq xmm0, rax");
+            }
+            Expression::String(s) => {
+                asm.push(format!("\tlea rax, {}[rip]", self.string_label(s)));
+            }
             Expression::Variable(v) => {
-                asm.push(format!(
-                    "\tmov eax, DWORD PTR {}[rbp]",
-                    self.scope_manager.get_offset(v)
+                asm.push(Self::load(
+                    &self.scope_manager.get_size(v),
+                    &self.scope_manager.get_address(v),
                 ));
             }
+            Expression::Member(_, _) => {
+                let size = self.type_of(expression);
+                asm.push_asm(self.generate_address(expression));
+                asm.push(Self::load(&size, "[rax]"));
+            }
+            Expression::UnaryOperator(UnOp::AddressOf, expr) => {
+                asm.push_asm(self.generate_address(expr));
+            }
+            Expression::UnaryOperator(UnOp::Dereference, expr) => {
+                let size = self.type_of(expression);
+                asm.push_asm(self.generate_expression(expr));
+                asm.push(Self::load(&size, "[rax]"));
+            }
             Expression::UnaryOperator(un_op, expr) => {
-                asm.push_asm(self.generate_expression(expr.borrow()));
-                asm.push_asm(self.generate_unary_expression(&un_op));
+                let size = self.type_of(expr).decay().promote();
+                asm.push_asm(self.generate_converted(expr.borrow(), &size));
+                asm.push_asm(self.generate_unary_expression(un_op, &size));
             }
             Expression::BinaryOperator(e1, _op @ BiOp::LogicalOr, e2) => {
                 let clause = self.create_label("clause");
                 let end = self.create_label("end");
 
                 //First expression
-                asm.push_asm(self.generate_expression(e1.borrow()));
-                asm.push_str("\ttest eax, eax");
+                asm.push_asm(self.generate_condition(e1.borrow()));
                 asm.push(format!("\tje {}", clause));
                 asm.push_str("\tmov eax, 1");
                 asm.push(format!("\tjmp {}", end));
 
                 //Second expression
                 asm.push(format!("{}:", clause));
-                asm.push_asm(self.generate_expression(e2.borrow()));
-                asm.push_str("\ttest eax, eax");
+                asm.push_asm(self.generate_condition(e2.borrow()));
                 asm.push_str("\tmov eax, 0");
                 asm.push_str("\tsetne al");
                 asm.push(format!("{}:", end));
@@ -421,141 +1547,291 @@ impl Generator {
                 let end = self.create_label("end");
 
                 //First expression
-                asm.push_asm(self.generate_expression(e1.borrow()));
-                asm.push_str("\ttest eax, eax");
+                asm.push_asm(self.generate_condition(e1.borrow()));
                 asm.push(format!("\tjne {}", clause));
+                asm.push_str("\tmov eax, 0");
                 asm.push(format!("\tjmp {}", end));
 
                 //Second expression
                 asm.push(format!("{}:", clause));
-                asm.push_asm(self.generate_expression(e2.borrow()));
-                asm.push_str("\ttest eax, eax");
+                asm.push_asm(self.generate_condition(e2.borrow()));
                 asm.push_str("\tmov eax, 0");
                 asm.push_str("\tsetne al");
                 asm.push(format!("{}:", end));
             }
+            Expression::BinaryOperator(e1, op @ BiOp::Addition, e2)
+            | Expression::BinaryOperator(e1, op @ BiOp::Minus, e2)
+                if self.type_of(e1).decay().is_pointer() || self.type_of(e2).decay().is_pointer() =>
+            {
+                asm.push_asm(self.generate_pointer_arithmetic(e1, op, e2));
+            }
             Expression::BinaryOperator(e1, op @ BiOp::Division, e2)
             | Expression::BinaryOperator(e1, op @ BiOp::Minus, e2)
-            | Expression::BinaryOperator(e1, op @ BiOp::Modulus, e2) => {
-                asm.push_asm(self.generate_expression(e2.borrow()));
-                asm.push(format!("\tpush rax"));
-                asm.push_asm(self.generate_expression(e1.borrow()));
-                asm.push(format!("\tpop rcx"));
-                asm.push_asm(self.generate_binary_operator(op));
+            | Expression::BinaryOperator(e1, op @ BiOp::Modulus, e2)
+            | Expression::BinaryOperator(e1, op @ BiOp::BitwiseShiftLeft, e2)
+            | Expression::BinaryOperator(e1, op @ BiOp::BitwiseShiftRight, e2) => {
+                // The count of a shift keeps its own type
+                let size = self.type_of(expression);
+                let count = match op {
+                    BiOp::BitwiseShiftLeft | BiOp::BitwiseShiftRight => self.type_of(e2).promote(),
+                    _ => size.clone(),
+                };
+                asm.push_asm(self.generate_converted(e2.borrow(), &count));
+                asm.push_asm(self.push_value(&count));
+                asm.push_asm(self.generate_converted(e1.borrow(), &size));
+                let pop = self.pop_register("rcx");
+                asm.push(pop);
+                asm.push_asm(self.generate_operator(op, &size));
             }
             Expression::BinaryOperator(e1, _op @ BiOp::Assign, e2) => {
-                asm.push_asm(self.generate_expression(e2.borrow()));
-                if let Expression::Variable(v) = e1.borrow() {
-                    asm.push(format!(
-                        "\tmov  DWORD PTR {}[rbp], eax",
-                        self.scope_manager.get_offset(v)
-                    ));
-                } else {
-                    panic!("")
-                }
+                asm.push_asm(self.generate_assignment(e1, e2));
             }
             Expression::BinaryOperator(e1, op, e2) => {
-                asm.push_asm(self.generate_expression(e1.borrow()));
-                asm.push(format!("\tpush rax"));
-                asm.push_asm(self.generate_expression(e2.borrow()));
-                asm.push(format!("\tpop rcx"));
-                asm.push_asm(self.generate_binary_operator(op));
-            }
-            Expression::Assign(variable, expression) => {
-                asm.push_asm(self.generate_expression(expression));
-                asm.push(format!(
-                    "\tmov DWORD PTR {}[rbp], eax",
-                    self.scope_manager.get_offset(variable)
-                ));
+                // Pointers are compared on their 64 bits
+                let size = match (self.type_of(e1).decay(), self.type_of(e2).decay()) {
+                    (size @ Size::Pointer(_, _), _) | (_, size @ Size::Pointer(_, _)) => size,
+                    (s1, s2) => s1.common(s2),
+                };
+                asm.push_asm(self.generate_converted(e1.borrow(), &size));
+                asm.push_asm(self.push_value(&size));
+                asm.push_asm(self.generate_converted(e2.borrow(), &size));
+                let pop = self.pop_register("rcx");
+                asm.push(pop);
+                asm.push_asm(self.generate_operator(op, &size));
+            }
+            Expression::Assign(lvalue, expression, _) => {
+                asm.push_asm(self.generate_assignment(lvalue, expression));
+            }
+            Expression::CompoundAssign(lvalue, op, expression, _) => {
+                asm.push_asm(self.generate_compound_assignment(lvalue, op, expression, false));
+            }
+            Expression::AssignPost(lvalue, op, expression, _) => {
+                asm.push_asm(self.generate_compound_assignment(lvalue, op, expression, true));
             }
             Expression::CondExp(condition, body, else_) => {
                 let post_conditional = self.create_label("post_conditional");
                 let else_conditional = self.create_label("else_conditional");
+                let size = self.type_of(expression);
 
-                asm.push_asm(self.generate_expression(condition));
-                asm.push_str("\tcmp eax, 0");
+                asm.push_asm(self.generate_condition(condition));
                 asm.push(format!("je {}", else_conditional));
-                asm.push_asm(self.generate_expression(body));
+                asm.push_asm(self.generate_converted(body, &size));
                 asm.push(format!("jmp {}", post_conditional));
 
                 asm.push(format!("{}:", else_conditional));
-                asm.push_asm(self.generate_expression(else_));
+                asm.push_asm(self.generate_converted(else_, &size));
                 asm.push(format!("jmp {}", post_conditional));
                 asm.push(format!("{}:", post_conditional));
             }
-            _ => {
-                println!("NOT EVALUATED >>> {:?}", expression);
+            Expression::Call(name, arguments, _) => {
+                asm.push_asm(self.generate_call(name, arguments));
+            }
+            Expression::Cast(size, expr) => {
+                asm.push_asm(self.generate_converted(expr, size));
+            }
+            Expression::Comma(e1, e2) => {
+                asm.push_asm(self.generate_expression(e1));
+                asm.push_asm(self.generate_expression(e2));
             }
         }
 
         asm
     }
 
-    fn generate_binary_operator(&self, op: &BiOp) -> Assembly {
+    /// Classes of the arguments of a call following the System V AMD64
+    /// calling convention: the integer and floating arguments fill their own
+    /// registers, the others go on the stack.
+    fn classify(sizes: &[Size]) -> Vec<Class> {
+        let (mut integers, mut floatings, mut slots) = (0, 0, 0);
+        sizes
+            .iter()
+            .map(|size| {
+                if size.is_floating() && floatings < SSE_REGISTERS {
+                    floatings += 1;
+                    Class::Sse(floatings - 1)
+                } else if !size.is_floating() && integers < ARGUMENT_REGISTERS.len() {
+                    integers += 1;
+                    Class::Integer(&ARGUMENT_REGISTERS[integers - 1])
+                } else {
+                    slots += 1;
+                    Class::Memory(slots - 1)
+                }
+            })
+            .collect()
+    }
+
+    /// Calls `name` following the System V AMD64 calling convention: the
+    /// stack arguments are pushed right to left so that the first one is on
+    /// top of the stack, then the register ones are evaluated and popped in
+    /// their registers.
+    fn generate_call(&mut self, name: &str, arguments: &[Expression]) -> Assembly {
         let mut asm = Assembly::new();
+        let mut sizes = self.parameter_sizes[name].clone();
+        // The extra arguments of a variadic function get the default
+        // argument promotions
+        for argument in &arguments[sizes.len()..] {
+            let size = match self.type_of(argument).decay() {
+                Size::Float => Size::Double,
+                size => size.promote(),
+            };
+            sizes.push(size);
+        }
+        let classes = Self::classify(&sizes);
+        let stack_arguments = classes
+            .iter()
+            .filter(|class| matches!(class, Class::Memory(_)))
+            .count();
+        let floatings = classes
+            .iter()
+            .filter(|class| matches!(class, Class::Sse(_)))
+            .count();
+
+        // rsp must be 16 bytes aligned on `call`, stack arguments included
+        let padding = (self.depth + stack_arguments) % 2;
+        if padding == 1 {
+            asm.push_str("\tsub rsp, 8");
+            self.depth += 1;
+        }
 
-        match op {
-            BiOp::Addition => {
-                asm.push_str("\tadd rax, rcx");
-            }
-            BiOp::Multiplication => {
-                asm.push_str("\timul rax, rcx");
+        for on_stack in [true, false] {
+            for ((argument, size), class) in arguments.iter().zip(&sizes).zip(&classes).rev() {
+                if matches!(class, Class::Memory(_)) == on_stack {
+                    asm.push_asm(self.generate_converted(argument, size));
+                    asm.push_asm(self.push_value(size));
+                }
             }
-            BiOp::Division => {
-                asm.push_str("\tmov rdx, 0");
-                asm.push_str("\tidiv rcx");
+        }
+        for class in &classes {
+            match class {
+                Class::Integer(registers) => {
+                    let pop = self.pop_register(registers[0]);
+                    asm.push(pop);
+                }
+                Class::Sse(index) => {
+                    let pop = self.pop_register("rax");
+                    asm.push(pop);
+                    asm.push(format!("\tmovq xmm{}, rax", index));
+                }
+                Class::Memory(_) => {}
             }
-            BiOp::Minus => asm.push_str("\tsub rax, rcx"),
+        }
+
+        // al holds the number of vector registers used by variadic functions
+        asm.push(format!("\tmov eax, {}", floatings));
+        if self.functions.contains(name) {
+            asm.push(format!("\tcall {}", name));
+        } else {
+            asm.push(format!("\tcall {}@PLT", name));
+        }
+
+        let cleanup = stack_arguments + padding;
+        if cleanup > 0 {
+            asm.push(format!("\tadd rsp, {}", 8 * cleanup));
+            self.depth -= cleanup;
+        }
+        asm.push_asm(Self::promote(&self.return_sizes[name]));
+        asm
+    }
+
+    /// Operator applied to the popped operand in `rcx` and to `rax`, or to
+    /// `xmm1` and `xmm0` for a floating type.
+    fn generate_operator(&self, op: &BiOp, size: &Size) -> Assembly {
+        if size.is_floating() {
+            let mut asm = Assembly::new();
+            asm.push_str("\tmovq xmm1, rcx");
+            asm.push_asm(Self::generate_floating_operator(op, size));
+            asm
+        } else {
+            self.generate_binary_operator(op, size)
+        }
+    }
+
+    /// Floating operator applied to `xmm1` (left operand) and `xmm0`, except
+    /// for the non commutative ones which get the right operand in `xmm1`.
+    /// The comparisons are false on NaN, the unordered result setting the
+    /// zero, parity and carry flags.
+    fn generate_floating_operator(op: &BiOp, size: &Size) -> Assembly {
+        let mut asm = Assembly::new();
+        let sse = Self::sse(size);
+        let compare = |asm: &mut Assembly, left: &str, right: &str, set: &str| {
+            asm.push(format!("\tucomi{} {}, {}", sse, left, right));
+            asm.push_str("\tmov eax, 0");
+            asm.push(format!("\t{} al", set));
+        };
+
+        match op {
+            BiOp::Addition => asm.push(format!("\tadd{} xmm0, xmm1", sse)),
+            BiOp::Multiplication => asm.push(format!("\tmul{} xmm0, xmm1", sse)),
+            BiOp::Minus => asm.push(format!("\tsub{} xmm0, xmm1", sse)),
+            BiOp::Division => asm.push(format!("\tdiv{} xmm0, xmm1", sse)),
+            BiOp::GreaterThan => compare(&mut asm, "xmm1", "xmm0", "seta"),
+            BiOp::GreaterOrEqual => compare(&mut asm, "xmm1", "xmm0", "setae"),
+            BiOp::LessThan => compare(&mut asm, "xmm0", "xmm1", "seta"),
+            BiOp::LessOrEqual => compare(&mut asm, "xmm0", "xmm1", "setae"),
             BiOp::Equal => {
-                asm.push_str("\tcmp ecx, eax");
-                asm.push_str("\tmov eax, 0");
-                asm.push_str("\tsete al");
+                compare(&mut asm, "xmm1", "xmm0", "sete");
+                asm.push_str("\tsetnp cl");
+                asm.push_str("\tand al, cl");
             }
             BiOp::NotEqual => {
-                asm.push_str("\tcmp ecx, eax");
-                asm.push_str("\tmov eax, 0");
-                asm.push_str("\tsetne al");
-            }
-            BiOp::LessThan => {
-                asm.push_str("\tcmp ecx, eax");
-                asm.push_str("\tmov eax, 0");
-                asm.push_str("\tsetl al");
-            }
-            BiOp::LessOrEqual => {
-                asm.push_str("\tcmp ecx, eax");
-                asm.push_str("\tmov eax, 0");
-                asm.push_str("\tsetle al");
-            }
-            BiOp::GreaterThan => {
-                asm.push_str("\tcmp ecx, eax");
-                asm.push_str("\tmov eax, 0");
-                asm.push_str("\tsetg al");
+                compare(&mut asm, "xmm1", "xmm0", "setne");
+                asm.push_str("\tsetp cl");
+                asm.push_str("\tor al, cl");
             }
-            BiOp::GreaterOrEqual => {
-                asm.push_str("\tcmp ecx, eax");
-                asm.push_str("\tmov eax, 0");
-                asm.push_str("\tsetge al");
-            }
-            //			BiOp::Assign => {}
-            BiOp::BitwiseAND => {
-                asm.push_str("\tand eax, rcx");
-            }
-            BiOp::BitwiseOR => {
-                asm.push_str("\tor eax, rcx");
-            }
-            BiOp::BitwiseXOR => {
-                asm.push_str("\txor eax, rcx");
+            _ => panic!("invalid operands to {:?} ({:?})", op, size),
+        }
+        asm
+    }
+
+    /// Operator applied to `rcx` (left operand) and `rax`, except for the
+    /// non commutative ones which get the right operand in `rcx`. Both have
+    /// the type `size`; unsigned integers and pointers are compared without
+    /// sign.
+    fn generate_binary_operator(&self, op: &BiOp, size: &Size) -> Assembly {
+        let mut asm = Assembly::new();
+        let (rax, rcx) = (Self::register(size, &RAX), Self::register(size, &RCX));
+        let unsigned = size.is_unsigned() || size.is_pointer();
+        let compare = |asm: &mut Assembly, set: &str| {
+            asm.push(format!("\tcmp {}, {}", rcx, rax));
+            asm.push_str("\tmov eax, 0");
+            asm.push(format!("\t{} al", set));
+        };
+        // The dividend is extended into `rdx`
+        let divide = |asm: &mut Assembly| {
+            if unsigned {
+                asm.push_str("\txor edx, edx");
+                asm.push(format!("\tdiv {}", rcx));
+            } else {
+                asm.push_str(if rax == "rax" { "\tcqo" } else { "\tcdq" });
+                asm.push(format!("\tidiv {}", rcx));
             }
+        };
+
+        match op {
+            BiOp::Addition => asm.push(format!("\tadd {}, {}", rax, rcx)),
+            BiOp::Multiplication => asm.push(format!("\timul {}, {}", rax, rcx)),
+            BiOp::Division => divide(&mut asm),
+            BiOp::Minus => asm.push(format!("\tsub {}, {}", rax, rcx)),
+            BiOp::Equal => compare(&mut asm, "sete"),
+            BiOp::NotEqual => compare(&mut asm, "setne"),
+            BiOp::LessThan => compare(&mut asm, if unsigned { "setb" } else { "setl" }),
+            BiOp::LessOrEqual => compare(&mut asm, if unsigned { "setbe" } else { "setle" }),
+            BiOp::GreaterThan => compare(&mut asm, if unsigned { "seta" } else { "setg" }),
+            BiOp::GreaterOrEqual => compare(&mut asm, if unsigned { "setae" } else { "setge" }),
+            BiOp::BitwiseAND => asm.push(format!("\tand {}, {}", rax, rcx)),
+            BiOp::BitwiseOR => asm.push(format!("\tor {}, {}", rax, rcx)),
+            BiOp::BitwiseXOR => asm.push(format!("\txor {}, {}", rax, rcx)),
             BiOp::Assign => {
                 asm.push_str("\tmov rcx, rax");
             }
 
-            BiOp::BitwiseShiftLeft => {}
-            BiOp::BitwiseShiftRight => {}
+            // The count of a shift is taken from `cl`
+            BiOp::BitwiseShiftLeft => asm.push(format!("\tsal {}, cl", rax)),
+            BiOp::BitwiseShiftRight if unsigned => asm.push(format!("\tshr {}, cl", rax)),
+            BiOp::BitwiseShiftRight => asm.push(format!("\tsar {}, cl", rax)),
             BiOp::Modulus => {
-                asm.push_str("\tmov rdx, 0");
-                asm.push_str("\tidiv rcx");
-                asm.push_str("\tmov rax, rdx")
+                divide(&mut asm);
+                asm.push(format!("\tmov {}, {}", rax, Self::register(size, &RDX)));
             }
             _ => {
                 //                println!("Here ???");
@@ -565,19 +1841,43 @@ impl Generator {
         asm
     }
 
-    fn generate_unary_expression(&self, operator: &UnOp) -> Assembly {
+    fn generate_unary_expression(&self, operator: &UnOp, size: &Size) -> Assembly {
         let mut asm = Assembly::new();
+        match (operator, size) {
+            // The sign bit is flipped
+            (UnOp::Negation, Size::Float) => {
+                asm.push_str("\tmovd eax, xmm0");
+                asm.push_str("\tbtc eax, 31");
+                asm.push_str("\tmovd xmm0, eax");
+                return asm;
+            }
+            (UnOp::Negation, Size::Double) => {
+                asm.push_str("\tmovq rax, xmm0");
+                asm.push_str("\tbtc rax, 63");
+                asm.push_str("\tmovq xmm0, rax");
+                return asm;
+            }
+            (UnOp::LogicalNegation, size) if size.is_floating() => {
+                asm.push_str("\txorps xmm1, xmm1");
+                asm.push_asm(Self::generate_floating_operator(&BiOp::Equal, size));
+                return asm;
+            }
+            _ => {}
+        }
         match operator {
             UnOp::Negation => {
-                asm.push(format!("\tneg eax"));
+                asm.push(format!("\tneg {}", Self::register(size, &RAX)));
             }
             UnOp::Bitwise => {
-                asm.push(format!("\tnot eax"));
+                asm.push(format!("\tnot {}", Self::register(size, &RAX)));
             }
             UnOp::LogicalNegation => {
-                asm.push(format!("\tcmp eax, 0"));
-                asm.push(format!("\tmov eax, 0"));
-                asm.push(format!("\tsete al"));
+                asm.push(format!("\tcmp {}, 0", Self::register(size, &RAX)));
+                asm.push_str("\tmov eax, 0");
+                asm.push_str("\tsete al");
+            }
+            UnOp::AddressOf | UnOp::Dereference => {
+                panic!("{:?} is generated with its operand", operator)
             }
         }
         asm
@@ -0,0 +1,794 @@
+use crate::assembly::Generator;
+use crate::data::{
+    BiOp, Compound, Declarations, Declare, Expression, Function, Initializer, Location, Program,
+    Qualifiers, Size, Statement, Storage, UnOp, Variable,
+};
+use crate::parser::ParseError;
+use std::collections::{HashMap, HashSet};
+
+/// Semantic pass run between the parser and the generator, so that the
+/// generator only ever sees a consistent program.
+pub struct Checker<'a> {
+    functions: HashMap<&'a str, &'a Function>,
+    /// File scope objects declared so far, with whether they have internal
+    /// linkage and whether they are initialized.
+    globals: HashMap<&'a str, (&'a Variable, bool, bool)>,
+    /// Number of loops enclosing the statement being checked.
+    loops: usize,
+    /// Promoted type and case values of the enclosing switches, and whether
+    /// they have a default label.
+    switches: Vec<(Size, HashSet<i64>, bool)>,
+    /// Labels of the function being checked.
+    labels: HashSet<&'a str>,
+    /// Variables of the enclosing scopes, the globals first, with whether
+    /// they are declared `extern`.
+    scopes: Vec<HashMap<String, (Variable, bool)>>,
+    /// Return size of the function being checked.
+    return_size: Size,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new() -> Checker<'a> {
+        Checker {
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            loops: 0,
+            switches: vec![],
+            labels: HashSet::new(),
+            scopes: vec![],
+            return_size: Size::Int,
+        }
+    }
+
+    pub fn check(&mut self, program: &'a Program) -> Result<(), ParseError> {
+        for function in &program.functions {
+            self.declare_function(function)?;
+        }
+        let mut globals = HashMap::new();
+        for Declare::Declare(storage, declarators) in &program.globals {
+            for (variable, initializer, location) in declarators {
+                self.declare_global(*storage, variable, initializer.is_some(), *location)?;
+                globals.insert(variable.name.clone(), (variable.clone(), false));
+            }
+        }
+        self.scopes = vec![globals];
+        for Declare::Declare(_, declarators) in &program.globals {
+            for (variable, initializer, location) in declarators {
+                self.check_initializer(&variable.size, initializer, *location)?;
+            }
+        }
+        for function in &program.functions {
+            if let Some(compounds) = &function.compounds {
+                self.check_parameters(function)?;
+                self.return_size = function.size.clone();
+                self.scopes.push(
+                    function
+                        .variables
+                        .iter()
+                        .map(|variable| (variable.name.clone(), (variable.clone(), false)))
+                        .collect(),
+                );
+                self.labels.clear();
+                for compound in compounds {
+                    if let Compound::Statement(statement) = compound {
+                        self.declare_labels(statement)?;
+                    }
+                }
+                for compound in compounds {
+                    self.check_compound(compound)?;
+                }
+                self.scopes.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Every declaration of a function must agree with the previous ones,
+    /// and only one of them may have a body.
+    fn declare_function(&mut self, function: &'a Function) -> Result<(), ParseError> {
+        Self::check_symbol(&function.name, function.location)?;
+        if let Some(previous) = self.functions.get(function.name.as_str()) {
+            let same_parameters = previous.variables.len() == function.variables.len()
+                && previous.variadic == function.variadic
+                && previous
+                    .variables
+                    .iter()
+                    .zip(&function.variables)
+                    .all(|(a, b)| a.size == b.size);
+            if previous.size != function.size || !same_parameters {
+                return Err(ParseError::new_with_location(
+                    format!("conflicting types for `{}`", function.name),
+                    function.location,
+                ));
+            }
+            if previous.compounds.is_some() && function.compounds.is_some() {
+                return Err(ParseError::new_with_location(
+                    format!("redefinition of `{}`", function.name),
+                    function.location,
+                ));
+            }
+            if previous.compounds.is_some() {
+                return Ok(());
+            }
+        }
+        self.functions.insert(&function.name, function);
+        Ok(())
+    }
+
+    /// Every declaration of an object at file scope must agree with the
+    /// previous ones on its type and its linkage, and only one of them may
+    /// have an initializer. An `extern` declaration keeps the linkage of a
+    /// previous `static` one.
+    fn declare_global(
+        &mut self,
+        storage: Option<Storage>,
+        variable: &'a Variable,
+        initialized: bool,
+        location: Location,
+    ) -> Result<(), ParseError> {
+        Self::check_symbol(&variable.name, location)?;
+        let name = variable.name.as_str();
+        let internal = storage == Some(Storage::Static);
+        let message = match self.globals.get(name) {
+            _ if self.functions.contains_key(name) => {
+                Some(format!("`{}` redeclared as a different kind of symbol", name))
+            }
+            Some((previous, _, _))
+                if previous.size != variable.size || previous.qualifiers != variable.qualifiers =>
+            {
+                Some(format!("conflicting types for `{}`", name))
+            }
+            Some((_, false, _)) if internal => Some(format!(
+                "static declaration of `{}` follows non-static declaration",
+                name
+            )),
+            Some((_, true, _)) if storage.is_none() => Some(format!(
+                "non-static declaration of `{}` follows static declaration",
+                name
+            )),
+            Some((_, _, true)) if initialized => Some(format!("redefinition of `{}`", name)),
+            _ => None,
+        };
+        if let Some(message) = message {
+            return Err(ParseError::new_with_location(message, location));
+        }
+        let (internal, initialized) = match self.globals.get(name) {
+            Some(&(_, previous_internal, previous_initialized)) => {
+                (previous_internal, previous_initialized || initialized)
+            }
+            None => (internal, initialized),
+        };
+        self.globals.insert(name, (variable, internal, initialized));
+        Ok(())
+    }
+
+    /// Names of the objects and functions with linkage are the symbols of
+    /// the assembly output.
+    fn check_symbol(name: &str, location: Location) -> Result<(), ParseError> {
+        if Generator::is_reserved_symbol(name) {
+            return Err(ParseError::new_with_location(
+                format!("`{}` is reserved by the assembler and cannot name a symbol", name),
+                location,
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_parameters(&self, function: &Function) -> Result<(), ParseError> {
+        for (index, variable) in function.variables.iter().enumerate() {
+            if variable.name.is_empty() {
+                return Err(ParseError::new_with_location(
+                    format!(
+                        "parameter {} of `{}` has no name",
+                        index + 1,
+                        function.name
+                    ),
+                    function.location,
+                ));
+            }
+            if function.variables[..index]
+                .iter()
+                .any(|v| v.name == variable.name)
+            {
+                return Err(ParseError::new_with_location(
+                    format!(
+                        "redefinition of parameter `{}` in `{}`",
+                        variable.name, function.name
+                    ),
+                    function.location,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Labels are resolved per function, a `goto` may jump forward.
+    fn declare_labels(&mut self, statement: &'a Statement) -> Result<(), ParseError> {
+        match statement {
+            Statement::Label(name, body, location) => {
+                if !self.labels.insert(name) {
+                    return Err(ParseError::new_with_location(
+                        format!("duplicate label `{}`", name),
+                        *location,
+                    ));
+                }
+                self.declare_labels(body)
+            }
+            Statement::If(_, body, else_statement, _) => {
+                self.declare_labels(body)?;
+                if let Some(else_statement) = else_statement {
+                    self.declare_labels(else_statement)?;
+                }
+                Ok(())
+            }
+            Statement::Compound(compounds) | Statement::Do(compounds, _, _) => {
+                for compound in compounds {
+                    if let Compound::Statement(statement) = compound {
+                        self.declare_labels(statement)?;
+                    }
+                }
+                Ok(())
+            }
+            Statement::For(_, _, _, body, _)
+            | Statement::ForDecl(_, _, _, body, _)
+            | Statement::While(_, body, _)
+            | Statement::Switch(_, body, _)
+            | Statement::Case(_, body, _)
+            | Statement::Default(body, _) => self.declare_labels(body),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_compound(&mut self, compound: &Compound) -> Result<(), ParseError> {
+        match compound {
+            Compound::Statement(statement) => self.check_statement(statement),
+            Compound::Declare(declare) => self.check_declaration(declare),
+        }
+    }
+
+    fn check_declaration(&mut self, declare: &Declare) -> Result<(), ParseError> {
+        let Declare::Declare(storage, declarators) = declare;
+        let external = *storage == Some(Storage::Extern);
+        for (variable, initializer, location) in declarators {
+            if external {
+                Self::check_symbol(&variable.name, *location)?;
+            }
+            if let Some(scope) = self.scopes.last_mut() {
+                // Only an external object may be declared again in its block
+                match scope.get(&variable.name) {
+                    Some((previous, true)) if external && previous.size != variable.size => {
+                        return Err(ParseError::new_with_location(
+                            format!("conflicting types for `{}`", variable.name),
+                            *location,
+                        ))
+                    }
+                    Some((_, true)) if external => {}
+                    Some(_) => {
+                        return Err(ParseError::new_with_location(
+                            format!("redeclaration of `{}`", variable.name),
+                            *location,
+                        ))
+                    }
+                    None => {}
+                }
+                scope.insert(variable.name.clone(), (variable.clone(), external));
+            }
+            self.check_initializer(&variable.size, initializer, *location)?;
+        }
+        Ok(())
+    }
+
+    fn check_initializer(
+        &mut self,
+        size: &Size,
+        initializer: &Option<Initializer>,
+        location: Location,
+    ) -> Result<(), ParseError> {
+        match initializer {
+            Some(Initializer::Expression(expression)) => {
+                self.check_expression(expression, location)?;
+                self.check_conversion(size, expression, "initialization", location)
+            }
+            Some(Initializer::List(entries)) => {
+                for (_, size, expression) in entries {
+                    self.check_expression(expression, location)?;
+                    self.check_conversion(size, expression, "initialization", location)?;
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the statements of a block in a scope of their own.
+    fn check_block(&mut self, compounds: &[Compound]) -> Result<(), ParseError> {
+        self.scopes.push(HashMap::new());
+        let result = compounds
+            .iter()
+            .try_for_each(|compound| self.check_compound(compound));
+        self.scopes.pop();
+        result
+    }
+
+    fn check_statement(&mut self, statement: &Statement) -> Result<(), ParseError> {
+        match statement {
+            Statement::Return(expression, location) => match expression {
+                Some(_) if self.return_size == Size::Void => Err(ParseError::new_with_location(
+                    "`return` with a value, in function returning void".to_string(),
+                    *location,
+                )),
+                Some(expression) => {
+                    self.check_expression(expression, *location)?;
+                    let size = self.return_size.clone();
+                    self.check_conversion(&size, expression, "return", *location)
+                }
+                None if self.return_size != Size::Void => Err(ParseError::new_with_location(
+                    "`return` with no value, in function returning non-void".to_string(),
+                    *location,
+                )),
+                None => Ok(()),
+            },
+            Statement::Expression(expression, location) => {
+                if let Some(expression) = expression {
+                    self.check_expression(expression, *location)?;
+                }
+                Ok(())
+            }
+            Statement::If(condition, body, else_statement, location) => {
+                self.check_condition(condition, *location)?;
+                self.check_statement(body)?;
+                if let Some(else_statement) = else_statement {
+                    self.check_statement(else_statement)?;
+                }
+                Ok(())
+            }
+            Statement::Compound(compounds) => self.check_block(compounds),
+            Statement::For(initial, condition, post_expression, body, location) => {
+                if let Some(initial) = initial {
+                    self.check_expression(initial, *location)?;
+                }
+                self.check_condition(condition, *location)?;
+                if let Some(post_expression) = post_expression {
+                    self.check_expression(post_expression, *location)?;
+                }
+                self.check_loop_body(body)
+            }
+            Statement::ForDecl(declare, condition, post_expression, body, location) => {
+                self.scopes.push(HashMap::new());
+                self.check_declaration(declare)?;
+                self.check_condition(condition, *location)?;
+                if let Some(post_expression) = post_expression {
+                    self.check_expression(post_expression, *location)?;
+                }
+                let result = self.check_loop_body(body);
+                self.scopes.pop();
+                result
+            }
+            Statement::While(condition, body, location) => {
+                self.check_condition(condition, *location)?;
+                self.check_loop_body(body)
+            }
+            Statement::Do(body, condition, location) => {
+                self.loops += 1;
+                self.check_block(body)?;
+                self.loops -= 1;
+                self.check_condition(condition, *location)
+            }
+            Statement::Switch(expression, body, location) => {
+                self.check_expression(expression, *location)?;
+                let size = self.value_type(expression, *location)?.promote();
+                if !size.is_integer() {
+                    return Err(ParseError::new_with_location(
+                        format!("switch quantity not an integer (`{:?}`)", size),
+                        *location,
+                    ));
+                }
+                self.switches.push((size, HashSet::new(), false));
+                let result = self.check_statement(body);
+                self.switches.pop();
+                result
+            }
+            Statement::Case(value, body, location) => {
+                let (size, values, _) = self.switches.last_mut().ok_or_else(|| {
+                    ParseError::new_with_location(
+                        "`case` label not within a switch statement".to_string(),
+                        *location,
+                    )
+                })?;
+                // Values are compared once converted to the type of the switch
+                let value = size.truncate(*value);
+                if !values.insert(value) {
+                    return Err(ParseError::new_with_location(
+                        format!("duplicate case value `{}`", value),
+                        *location,
+                    ));
+                }
+                self.check_statement(body)
+            }
+            Statement::Default(body, location) => {
+                let (_, _, default) = self.switches.last_mut().ok_or_else(|| {
+                    ParseError::new_with_location(
+                        "`default` label not within a switch statement".to_string(),
+                        *location,
+                    )
+                })?;
+                if *default {
+                    return Err(ParseError::new_with_location(
+                        "multiple default labels in one switch".to_string(),
+                        *location,
+                    ));
+                }
+                *default = true;
+                self.check_statement(body)
+            }
+            Statement::Goto(label, location) if !self.labels.contains(label.as_str()) => {
+                Err(ParseError::new_with_location(
+                    format!("label `{}` used but not defined", label),
+                    *location,
+                ))
+            }
+            Statement::Goto(_, _) => Ok(()),
+            Statement::Label(_, body, _) => self.check_statement(body),
+            Statement::Break(location) if self.loops == 0 && self.switches.is_empty() => {
+                Err(ParseError::new_with_location(
+                    "`break` outside of a loop or a switch".to_string(),
+                    *location,
+                ))
+            }
+            Statement::Continue(location) if self.loops == 0 => Err(
+                ParseError::new_with_location("`continue` outside of a loop".to_string(), *location),
+            ),
+            Statement::Break(_) | Statement::Continue(_) => Ok(()),
+        }
+    }
+
+    fn check_loop_body(&mut self, body: &Statement) -> Result<(), ParseError> {
+        self.loops += 1;
+        let result = self.check_statement(body);
+        self.loops -= 1;
+        result
+    }
+
+    /// Checks the operands of an expression, then types it. An error is
+    /// reported at `location`, the one of the nearest enclosing construct.
+    fn check_expression(
+        &mut self,
+        expression: &Expression,
+        location: Location,
+    ) -> Result<(), ParseError> {
+        match expression {
+            Expression::Int(_)
+            | Expression::UInt(_)
+            | Expression::Long(_)
+            | Expression::ULong(_)
+            | Expression::Float(_)
+            | Expression::Double(_)
+            | Expression::String(_)
+            | Expression::Variable(_)
+            | Expression::Enumerator(_, _) => Ok(()),
+            Expression::UnaryOperator(op, operand) => {
+                self.check_expression(operand, location)?;
+                self.check_operand(op, operand, location)
+            }
+            Expression::Member(expression, _) | Expression::Cast(_, expression) => {
+                self.check_expression(expression, location)
+            }
+            Expression::BinaryOperator(e1, op, e2) => {
+                self.check_expression(e1, location)?;
+                self.check_expression(e2, location)?;
+                self.check_operands(op, e1, e2, location)
+            }
+            Expression::Comma(e1, e2) => {
+                self.check_expression(e1, location)?;
+                self.check_expression(e2, location)
+            }
+            Expression::Assign(lvalue, value, location)
+            | Expression::CompoundAssign(lvalue, _, value, location)
+            | Expression::AssignPost(lvalue, _, value, location) => {
+                self.check_expression(lvalue, *location)?;
+                self.check_expression(value, *location)?;
+                // An array is converted to a pointer which is not an lvalue
+                if let Size::Array(_, _) = self.type_of(lvalue, *location)? {
+                    return Err(ParseError::new_with_location(
+                        "assignment to expression with array type".to_string(),
+                        *location,
+                    ));
+                }
+                if self.qualifiers_of(lvalue, *location)?.constant {
+                    let message = match lvalue.as_ref() {
+                        Expression::Variable(name) => {
+                            format!("assignment of read-only variable `{}`", name)
+                        }
+                        _ => "assignment of read-only location".to_string(),
+                    };
+                    return Err(ParseError::new_with_location(message, *location));
+                }
+                match expression {
+                    Expression::Assign(_, _, _) => {
+                        let size = self.type_of(lvalue, *location)?;
+                        self.check_conversion(&size, value, "assignment", *location)
+                    }
+                    // The result is stored in the lvalue, so only a pointer
+                    // offset by an integer stays a pointer
+                    Expression::CompoundAssign(_, op, _, _)
+                    | Expression::AssignPost(_, op, _, _) => {
+                        self.check_operands(op, lvalue, value, *location)?;
+                        let right = self.value_type(value, *location)?;
+                        if right.is_pointer() {
+                            let left = self.value_type(lvalue, *location)?;
+                            return Err(Self::invalid_operands(op, &left, &right, *location));
+                        }
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Expression::CondExp(condition, body, else_) => {
+                self.check_condition(condition, location)?;
+                self.check_expression(body, location)?;
+                self.check_expression(else_, location)?;
+                // Both operands may be void, not only one of them
+                let body_void = self.value_type(body, location)? == Size::Void;
+                if body_void != (self.value_type(else_, location)? == Size::Void) {
+                    return Err(Self::void_value(location));
+                }
+                Ok(())
+            }
+            Expression::Call(name, arguments, location) => {
+                let function = match self.functions.get(name.as_str()) {
+                    Some(function) => *function,
+                    None => {
+                        return Err(ParseError::new_with_location(
+                            format!("implicit declaration of function `{}`", name),
+                            *location,
+                        ))
+                    }
+                };
+                let expected = function.variables.len();
+                if arguments.len() < expected || !function.variadic && arguments.len() > expected
+                {
+                    return Err(ParseError::new_with_location(
+                        format!(
+                            "`{}` expects {}{} argument(s) but {} were given",
+                            name,
+                            if function.variadic { "at least " } else { "" },
+                            expected,
+                            arguments.len()
+                        ),
+                        *location,
+                    ));
+                }
+                for (index, argument) in arguments.iter().enumerate() {
+                    self.check_expression(argument, *location)?;
+                    let context = format!("passing argument {} of `{}`", index + 1, name);
+                    match function.variables.get(index) {
+                        Some(parameter) => {
+                            self.check_conversion(&parameter.size, argument, &context, *location)?
+                        }
+                        // Extra arguments of a variadic function
+                        None => match self.value_type(argument, *location)? {
+                            Size::Void => {
+                                return Err(ParseError::new_with_location(
+                                    format!("{} uses a void value", context),
+                                    *location,
+                                ))
+                            }
+                            Size::Struct(structure) => {
+                                return Err(ParseError::new_with_location(
+                                    format!(
+                                        "{} passes `{:?}` by value, which is not supported",
+                                        context, structure
+                                    ),
+                                    *location,
+                                ))
+                            }
+                            _ => {}
+                        },
+                    }
+                }
+                Ok(())
+            }
+        }?;
+        self.type_of(expression, location).map(|_| ())
+    }
+
+    /// Operand of a unary operator: a pointer is not negated nor complemented.
+    fn check_operand(
+        &self,
+        op: &UnOp,
+        operand: &Expression,
+        location: Location,
+    ) -> Result<(), ParseError> {
+        let size = self.value_type(operand, location)?;
+        if size == Size::Void {
+            return Err(Self::void_value(location));
+        }
+        let valid = match op {
+            UnOp::Negation => !size.is_pointer(),
+            UnOp::Bitwise => size.is_integer(),
+            _ => true,
+        };
+        if !valid {
+            return Err(ParseError::new_with_location(
+                format!("invalid operand to unary {} (`{:?}`)", op, size),
+                location,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Operands of a binary operator. The remainder, shift and bitwise
+    /// operators take integers. A pointer is only offset by an integer,
+    /// subtracted from a pointer or compared to a pointer or to a null pointer
+    /// constant.
+    fn check_operands(
+        &self,
+        op: &BiOp,
+        e1: &Expression,
+        e2: &Expression,
+        location: Location,
+    ) -> Result<(), ParseError> {
+        let (left, right) = (self.value_type(e1, location)?, self.value_type(e2, location)?);
+        if left == Size::Void || right == Size::Void {
+            return Err(Self::void_value(location));
+        }
+        let logical = matches!(op, BiOp::LogicalAnd | BiOp::LogicalOr);
+        let integers = matches!(
+            op,
+            BiOp::Modulus
+                | BiOp::BitwiseShiftLeft
+                | BiOp::BitwiseShiftRight
+                | BiOp::BitwiseAND
+                | BiOp::BitwiseOR
+                | BiOp::BitwiseXOR
+        );
+        let valid = match (&left, &right) {
+            _ if integers => left.is_integer() && right.is_integer(),
+            (Size::Pointer(_, _), Size::Pointer(_, _)) => {
+                op.is_comparison() || logical || *op == BiOp::Minus
+            }
+            (Size::Pointer(_, _), size) => match op {
+                BiOp::Addition | BiOp::Minus => size.is_integer(),
+                _ => logical || op.is_comparison() && Self::is_null_constant(e2),
+            },
+            (size, Size::Pointer(_, _)) => match op {
+                BiOp::Addition => size.is_integer(),
+                _ => logical || op.is_comparison() && Self::is_null_constant(e1),
+            },
+            _ => true,
+        };
+        if !valid {
+            return Err(Self::invalid_operands(op, &left, &right, location));
+        }
+        Ok(())
+    }
+
+    fn invalid_operands(op: &BiOp, left: &Size, right: &Size, location: Location) -> ParseError {
+        ParseError::new_with_location(
+            format!("invalid operands to binary {} (`{:?}` and `{:?}`)", op, left, right),
+            location,
+        )
+    }
+
+    /// Controlling expression of a statement or of `?:`, compared to 0.
+    fn check_condition(
+        &mut self,
+        condition: &Expression,
+        location: Location,
+    ) -> Result<(), ParseError> {
+        self.check_expression(condition, location)?;
+        match self.value_type(condition, location)? {
+            Size::Void => Err(Self::void_value(location)),
+            size if size.is_integer() || size.is_floating() || size.is_pointer() => Ok(()),
+            size => Err(ParseError::new_with_location(
+                format!("used `{:?}` value where scalar is required", size),
+                location,
+            )),
+        }
+    }
+
+    fn void_value(location: Location) -> ParseError {
+        ParseError::new_with_location(
+            "void value not ignored as it ought to be".to_string(),
+            location,
+        )
+    }
+
+    /// Integer constant expression of value 0, which converts to any pointer.
+    fn is_null_constant(expression: &Expression) -> bool {
+        expression.evaluate() == Some(0)
+    }
+
+    /// Implicit conversion of a value to `size`. Arithmetic types convert
+    /// to each other, a pointer to a pointer to the same type with at least
+    /// the same qualifiers or from and to `void *`, a null pointer constant to
+    /// any pointer and a struct only to itself. A string literal initializes
+    /// a character array.
+    fn check_conversion(
+        &self,
+        size: &Size,
+        expression: &Expression,
+        context: &str,
+        location: Location,
+    ) -> Result<(), ParseError> {
+        if self.type_of(expression, location)? == Size::Void {
+            return Err(ParseError::new_with_location(
+                format!("{} uses a void value", context),
+                location,
+            ));
+        }
+        let value = self.value_type(expression, location)?;
+        let arithmetic = |size: &Size| size.is_integer() || size.is_floating();
+        let compatible = match (size, &value) {
+            (Size::Array(_, _), _) => matches!(expression, Expression::String(_)),
+            (target, source) if arithmetic(target) && arithmetic(source) => true,
+            (Size::Pointer(target, _), Size::Pointer(source, _)) => {
+                target == source || **target == Size::Void || **source == Size::Void
+            }
+            (Size::Pointer(_, _), source) => {
+                source.is_integer() && Self::is_null_constant(expression)
+            }
+            (Size::Struct(_), source) => size == source,
+            _ => false,
+        };
+        if !compatible {
+            return Err(ParseError::new_with_location(
+                format!("incompatible types in {} (`{:?}` from `{:?}`)", context, size, value),
+                location,
+            ));
+        }
+        if let (Size::Pointer(_, target), Size::Pointer(_, source)) = (size, value) {
+            if !target.contains(source) {
+                let qualifier = if source.constant && !target.constant {
+                    "const"
+                } else {
+                    "volatile"
+                };
+                return Err(ParseError::new_with_location(
+                    format!(
+                        "{} discards `{}` qualifier from pointer target type",
+                        context, qualifier
+                    ),
+                    location,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn type_of(&self, expression: &Expression, location: Location) -> Result<Size, ParseError> {
+        expression
+            .type_of(self)
+            .map_err(|error| ParseError::new_with_location(error, location))
+    }
+
+    fn value_type(&self, expression: &Expression, location: Location) -> Result<Size, ParseError> {
+        expression
+            .value_type(self)
+            .map_err(|error| ParseError::new_with_location(error, location))
+    }
+
+    fn qualifiers_of(
+        &self,
+        expression: &Expression,
+        location: Location,
+    ) -> Result<Qualifiers, ParseError> {
+        expression
+            .qualifiers(self)
+            .map_err(|error| ParseError::new_with_location(error, location))
+    }
+}
+
+impl<'a> Declarations for Checker<'a> {
+    fn variable(&self, name: &str) -> Option<Variable> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .map(|(variable, _)| variable.clone())
+    }
+
+    fn function(&self, name: &str) -> Option<Size> {
+        self.functions.get(name).map(|function| function.size.clone())
+    }
+}
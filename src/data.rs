@@ -1,17 +1,258 @@
-use crate::tokenizer::{Keyword, TokenType};
+use crate::tokenizer::{Token, TokenType};
+use std::cell::RefCell;
+use std::fmt::{Error, Formatter};
+use std::rc::Rc;
 
 #[allow(dead_code)]
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Size {
     Int,
     Byte,
+    Short,
+    /// `long` and `long long`, both 64 bits wide.
+    Long,
+    UInt,
+    UByte,
+    UShort,
+    ULong,
+    Float,
+    Double,
+    /// Type of no value, incomplete.
+    Void,
+    /// Pointer to a value of the type, with the qualifiers of that value.
+    Pointer(Box<Size>, Qualifiers),
+    Array(Box<Size>, usize),
+    Struct(Rc<Struct>),
+}
+
+/// A struct or union type, shared by every use of its tag. The layout is
+/// filled in once the definition is parsed, so that a member can point to the
+/// struct being defined.
+pub struct Struct {
+    pub tag: String,
+    pub union: bool,
+    layout: RefCell<Option<Layout>>,
+}
+
+/// Members with their offsets, as laid out by the System V ABI: each member
+/// is aligned on its own alignment, and the struct is padded to a multiple of
+/// the largest one. The members of a union all start at offset 0.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub members: Vec<Member>,
+    pub size: i32,
+    pub alignment: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: String,
+    pub size: Size,
+    pub qualifiers: Qualifiers,
+    pub offset: i32,
+}
+
+/// Type qualifiers of an object. The generator performs every access to a
+/// volatile object, it neither caches nor drops one.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Qualifiers {
+    pub constant: bool,
+    pub volatile: bool,
+}
+
+impl Qualifiers {
+    pub fn union(self, other: Qualifiers) -> Qualifiers {
+        Qualifiers {
+            constant: self.constant || other.constant,
+            volatile: self.volatile || other.volatile,
+        }
+    }
+
+    /// Whether every qualifier of `other` is also one of these.
+    pub fn contains(self, other: Qualifiers) -> bool {
+        (self.constant || !other.constant) && (self.volatile || !other.volatile)
+    }
+}
+
+impl Struct {
+    pub fn new(tag: String, union: bool) -> Struct {
+        Struct {
+            tag,
+            union,
+            layout: RefCell::new(None),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.layout.borrow().is_some()
+    }
+
+    pub fn define(&self, layout: Layout) {
+        *self.layout.borrow_mut() = Some(layout);
+    }
+
+    pub fn layout(&self) -> Layout {
+        match self.layout.borrow().as_ref() {
+            Some(layout) => layout.clone(),
+            None => panic!("`{:?}` is an incomplete type", self),
+        }
+    }
+
+    pub fn member(&self, name: &str) -> Member {
+        match self.find_member(name) {
+            Some(member) => member,
+            None => panic!("`{:?}` has no member named `{}`", self, name),
+        }
+    }
+
+    /// Member of a complete struct, if it has one of this name.
+    pub fn find_member(&self, name: &str) -> Option<Member> {
+        self.layout().members.into_iter().find(|m| m.name == name)
+    }
+}
+
+/// Two struct types are the same only if they come from the same definition.
+impl PartialEq for Struct {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// Printed by tag only, a member may point to its own struct.
+impl std::fmt::Debug for Struct {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let keyword = if self.union { "union" } else { "struct" };
+        write!(f, "{} {}", keyword, self.tag)
+    }
 }
 
-impl From<Keyword> for Size {
-    fn from(keyword: Keyword) -> Self {
-        match keyword {
-            Keyword::Int => Size::Int,
-            _ => panic!("critical error for type `{:?}`", keyword),
+impl Layout {
+    pub fn new(members: Vec<Variable>, union: bool) -> Layout {
+        let mut offset = 0;
+        let mut size = 0;
+        let mut alignment = 1;
+        let members = members
+            .into_iter()
+            .map(|Variable { name, size: member_size, qualifiers }| {
+                let member_alignment = member_size.alignment();
+                alignment = alignment.max(member_alignment);
+                if union {
+                    offset = 0;
+                } else {
+                    offset = (offset + member_alignment - 1) / member_alignment * member_alignment;
+                }
+                let member = Member {
+                    name,
+                    offset,
+                    size: member_size,
+                    qualifiers,
+                };
+                offset += i32::from(&member.size);
+                size = size.max(offset);
+                member
+            })
+            .collect();
+        Layout {
+            members,
+            size: (size + alignment - 1) / alignment * alignment,
+            alignment,
+        }
+    }
+}
+
+impl Size {
+    pub fn pointer_to(size: Size) -> Size {
+        Size::Pointer(Box::new(size), Qualifiers::default())
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self, Size::Pointer(_, _))
+    }
+
+    /// An array used as a value is converted to a pointer to its first element.
+    pub fn decay(self) -> Size {
+        match self {
+            Size::Array(size, _) => Size::pointer_to(*size),
+            size => size,
+        }
+    }
+
+    /// Whether objects of this type can be defined; a struct is incomplete
+    /// until its members are known.
+    pub fn is_complete(&self) -> bool {
+        match self {
+            Size::Array(size, _) => size.is_complete(),
+            Size::Struct(s) => s.is_complete(),
+            Size::Void => false,
+            _ => true,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        !matches!(
+            self,
+            Size::Float
+                | Size::Double
+                | Size::Void
+                | Size::Pointer(_, _)
+                | Size::Array(_, _)
+                | Size::Struct(_)
+        )
+    }
+
+    /// Whether the type is floating, held in `xmm0`.
+    pub fn is_floating(&self) -> bool {
+        matches!(self, Size::Float | Size::Double)
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, Size::UInt | Size::UByte | Size::UShort | Size::ULong)
+    }
+
+    /// Integer promotion: the types narrower than an int are converted to int.
+    pub fn promote(self) -> Size {
+        match self {
+            Size::Byte | Size::UByte | Size::Short | Size::UShort => Size::Int,
+            size => size,
+        }
+    }
+
+    /// Usual arithmetic conversions of the operands of a binary operator. A
+    /// long holds every unsigned int, so it wins over it.
+    pub fn common(self, other: Size) -> Size {
+        match (self.promote(), other.promote()) {
+            (Size::Double, _) | (_, Size::Double) => Size::Double,
+            (Size::Float, _) | (_, Size::Float) => Size::Float,
+            (Size::ULong, _) | (_, Size::ULong) => Size::ULong,
+            (Size::Long, _) | (_, Size::Long) => Size::Long,
+            (Size::UInt, _) | (_, Size::UInt) => Size::UInt,
+            _ => Size::Int,
+        }
+    }
+
+    /// Value of an integer constant converted to this type.
+    pub fn truncate(&self, value: i64) -> i64 {
+        match self {
+            Size::Byte => value as i8 as i64,
+            Size::UByte => value as u8 as i64,
+            Size::Short => value as i16 as i64,
+            Size::UShort => value as u16 as i64,
+            Size::Int => value as i32 as i64,
+            Size::UInt => value as u32 as i64,
+            _ => value,
+        }
+    }
+
+    /// Whether the type is an aggregate, held in `rax` by its address.
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Size::Array(_, _) | Size::Struct(_))
+    }
+
+    pub fn alignment(&self) -> i32 {
+        match self {
+            Size::Array(size, _) => size.alignment(),
+            Size::Struct(s) => s.layout().alignment,
+            size => i32::from(size),
         }
     }
 }
@@ -20,15 +261,46 @@ impl From<Keyword> for Size {
 #[derive(Debug)]
 pub struct Program {
     pub functions: Vec<Function>,
-    pub globals: Vec<Variable>,
+    pub globals: Vec<Declare>,
+}
+
+/// Position of a construct in the source file, for diagnostics.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Location {
+    pub line: usize,
+    pub position: usize,
+}
+
+impl From<&Token> for Location {
+    fn from(token: &Token) -> Self {
+        Location {
+            line: token.line,
+            position: token.position,
+        }
+    }
 }
 
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct Function {
     pub name: String,
+    pub size: Size,
     pub variables: Vec<Variable>,
-    pub compounds: Vec<Compound>,
+    /// Whether `...` ends the parameters.
+    pub variadic: bool,
+    /// `None` for a prototype, whose body is provided by another object.
+    pub compounds: Option<Vec<Compound>>,
+    pub storage: Option<Storage>,
+    pub location: Location,
+}
+
+/// Storage class specifier of a declaration.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Storage {
+    /// Internal linkage at file scope, static storage duration in a block.
+    Static,
+    /// Declares an object defined elsewhere, possibly in another object file.
+    Extern,
 }
 
 #[allow(dead_code)]
@@ -36,20 +308,41 @@ pub struct Function {
 pub struct Variable {
     pub name: String,
     pub size: Size,
+    pub qualifiers: Qualifiers,
 }
 
-impl From<Size> for i32 {
-    fn from(s: Size) -> Self {
+/// Size in bytes of a value.
+impl From<&Size> for i32 {
+    fn from(s: &Size) -> Self {
         match s {
-            Size::Int => 8,
-            Size::Byte => 1,
+            Size::Int | Size::UInt | Size::Float => 4,
+            Size::Byte | Size::UByte => 1,
+            // As in GNU C, arithmetic on `void *` steps by a byte
+            Size::Void => 1,
+            Size::Short | Size::UShort => 2,
+            Size::Long | Size::ULong | Size::Double => 8,
+            Size::Pointer(_, _) => 8,
+            Size::Array(size, length) => i32::from(size.as_ref()) * *length as i32,
+            Size::Struct(s) => s.layout().size,
         }
     }
 }
 
 impl Variable {
     pub fn new(name: String, size: Size) -> Variable {
-        Variable { name, size }
+        Variable {
+            name,
+            size,
+            qualifiers: Qualifiers::default(),
+        }
+    }
+
+    /// The System V ABI aligns array variables of 16 bytes or more on 16 bytes.
+    pub fn alignment(&self) -> i32 {
+        match &self.size {
+            size @ Size::Array(_, _) if i32::from(size) >= 16 => 16.max(size.alignment()),
+            size => size.alignment(),
+        }
     }
 }
 
@@ -63,35 +356,58 @@ pub enum Compound {
 #[allow(dead_code)]
 #[derive(Debug)]
 pub enum Declare {
-    Declare(Variable, Option<Expression>),
+    /// Declarators of a declaration with their initializers, declared from
+    /// left to right.
+    Declare(Option<Storage>, Vec<(Variable, Option<Initializer>, Location)>),
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum Initializer {
+    Expression(Expression),
+    /// Brace-enclosed list, flattened by the parser into the scalars it sets
+    /// with their offset in the object. The other bytes are zero.
+    List(Vec<(i32, Size, Expression)>),
 }
 
 #[allow(dead_code)]
 #[derive(Debug)]
 pub enum Statement {
-    Return(Expression),
-    Expression(Option<Expression>),
-    If(Expression, Box<Statement>, Option<Box<Statement>>),
+    Return(Option<Expression>, Location),
+    Expression(Option<Expression>, Location),
+    If(Expression, Box<Statement>, Option<Box<Statement>>, Location),
     Compound(Vec<Compound>), // { .. }
     For(
         Option<Expression>,
         Expression,
         Option<Expression>,
         Box<Statement>,
+        Location,
     ),
-    ForDecl(Declare, Expression, Option<Expression>, Box<Statement>),
-    While(Expression, Box<Statement>),
-    Do(Vec<Compound>, Expression),
-    Break,
-    Continue,
+    ForDecl(Declare, Expression, Option<Expression>, Box<Statement>, Location),
+    While(Expression, Box<Statement>, Location),
+    Do(Vec<Compound>, Expression, Location),
+    Break(Location),
+    Continue(Location),
+    Switch(Expression, Box<Statement>, Location),
+    /// Statement labeled by a case of the enclosing switch, whose value is
+    /// folded by the parser and converted to the promoted type of the
+    /// controlling expression.
+    Case(i64, Box<Statement>, Location),
+    Default(Box<Statement>, Location),
+    Goto(String, Location),
+    /// Statement labeled by a name, the target of a `goto` in its function.
+    Label(String, Box<Statement>, Location),
 }
 
 #[allow(dead_code)]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum UnOp {
     Negation,
     Bitwise,
     LogicalNegation,
+    AddressOf,
+    Dereference,
 }
 
 impl From<TokenType> for UnOp {
@@ -100,13 +416,29 @@ impl From<TokenType> for UnOp {
             TokenType::Minus => UnOp::Negation,
             TokenType::Bitwise => UnOp::Bitwise,
             TokenType::LogicalNegation => UnOp::LogicalNegation,
+            TokenType::BitwiseAND => UnOp::AddressOf,
+            TokenType::Multiplication => UnOp::Dereference,
             _ => panic!("critical error"),
         }
     }
 }
 
+/// Spelled as in the source, for the diagnostics.
+impl std::fmt::Display for UnOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let symbol = match self {
+            UnOp::Negation => "-",
+            UnOp::Bitwise => "~",
+            UnOp::LogicalNegation => "!",
+            UnOp::AddressOf => "&",
+            UnOp::Dereference => "*",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
 #[allow(dead_code)]
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub enum BiOp {
     Minus,
     Addition,
@@ -133,6 +465,36 @@ pub enum BiOp {
     Decrement,
 }
 
+impl BiOp {
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BiOp::Equal
+                | BiOp::NotEqual
+                | BiOp::LessThan
+                | BiOp::LessOrEqual
+                | BiOp::GreaterThan
+                | BiOp::GreaterOrEqual
+        )
+    }
+
+    /// Operators whose operands undergo the usual arithmetic conversions and
+    /// whose result has their common type.
+    pub fn is_arithmetic(&self) -> bool {
+        matches!(
+            self,
+            BiOp::Addition
+                | BiOp::Minus
+                | BiOp::Multiplication
+                | BiOp::Division
+                | BiOp::Modulus
+                | BiOp::BitwiseAND
+                | BiOp::BitwiseOR
+                | BiOp::BitwiseXOR
+        )
+    }
+}
+
 impl From<TokenType> for BiOp {
     fn from(token: TokenType) -> Self {
         match token {
@@ -153,6 +515,12 @@ impl From<TokenType> for BiOp {
             TokenType::AssignMinus => BiOp::Minus,
             TokenType::AssignDivide => BiOp::Division,
             TokenType::AssignMultiply => BiOp::Multiplication,
+            TokenType::AssignModulus => BiOp::Modulus,
+            TokenType::AssignBitwiseLeft => BiOp::BitwiseShiftLeft,
+            TokenType::AssignBitwiseRight => BiOp::BitwiseShiftRight,
+            TokenType::AssignAND => BiOp::BitwiseAND,
+            TokenType::AssignOR => BiOp::BitwiseOR,
+            TokenType::AssignXOR => BiOp::BitwiseXOR,
             TokenType::BitwiseAND => BiOp::BitwiseAND,
             TokenType::BitwiseOR => BiOp::BitwiseOR,
             TokenType::BitwiseXOR => BiOp::BitwiseXOR,
@@ -168,16 +536,343 @@ impl From<TokenType> for BiOp {
     }
 }
 
+/// Spelled as in the source, for the diagnostics.
+impl std::fmt::Display for BiOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let symbol = match self {
+            BiOp::Minus => "-",
+            BiOp::Addition => "+",
+            BiOp::Multiplication => "*",
+            BiOp::Division => "/",
+            BiOp::LogicalAnd => "&&",
+            BiOp::LogicalOr => "||",
+            BiOp::Equal => "==",
+            BiOp::NotEqual => "!=",
+            BiOp::LessThan => "<",
+            BiOp::LessOrEqual => "<=",
+            BiOp::GreaterThan => ">",
+            BiOp::GreaterOrEqual => ">=",
+            BiOp::Assign => "=",
+            BiOp::BitwiseAND => "&",
+            BiOp::BitwiseOR => "|",
+            BiOp::BitwiseXOR => "^",
+            BiOp::BitwiseShiftLeft => "<<",
+            BiOp::BitwiseShiftRight => ">>",
+            BiOp::Modulus => "%",
+            BiOp::QuestionMark => "?",
+            BiOp::Colon => ":",
+            BiOp::Increment => "++",
+            BiOp::Decrement => "--",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// Declarations visible where an expression is typed.
+pub trait Declarations {
+    /// Variable declared under the name.
+    fn variable(&self, name: &str) -> Option<Variable>;
+    /// Return type of the function.
+    fn function(&self, name: &str) -> Option<Size>;
+}
+
 #[allow(dead_code)]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     Int(i32),
+    UInt(u32),
+    Long(i64),
+    ULong(u64),
+    Float(f32),
+    Double(f64),
+    /// String literal, without its terminating null byte.
+    String(Vec<u8>),
     UnaryOperator(UnOp, Box<Expression>),
     BinaryOperator(Box<Expression>, BiOp, Box<Expression>),
-    Assign(String, Box<Expression>),
-    AssignPost(String, Box<Expression>),
+    /// Stores the value in the lvalue, and evaluates to the stored value.
+    Assign(Box<Expression>, Box<Expression>, Location),
+    /// Stores the operator applied to the lvalue and the value, the lvalue
+    /// being evaluated once; `++x` is `x += 1`.
+    CompoundAssign(Box<Expression>, BiOp, Box<Expression>, Location),
+    /// Same as `CompoundAssign` but evaluates to the value before the store,
+    /// as `x++`.
+    AssignPost(Box<Expression>, BiOp, Box<Expression>, Location),
     Variable(String),
+    /// Enumeration constant with its value.
+    Enumerator(String, i32),
+    /// Member of a struct; `p->m` is lowered to `(*p).m`.
+    Member(Box<Expression>, String),
     CondExp(Box<Expression>, Box<Expression>, Box<Expression>),
+    Call(String, Vec<Expression>, Location),
+    /// Conversion of the value to another scalar type.
+    Cast(Size, Box<Expression>),
+    /// Evaluates the left operand then the right one, whose value it yields.
+    Comma(Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Whether the expression designates an object that can be assigned.
+    pub fn is_lvalue(&self) -> bool {
+        match self {
+            Expression::Variable(_) | Expression::UnaryOperator(UnOp::Dereference, _) => true,
+            Expression::Member(expression, _) => expression.is_lvalue(),
+            _ => false,
+        }
+    }
+
+    /// Type of the expression, its pointers carrying the qualifiers of what
+    /// they point to. An invalid expression gets a diagnostic.
+    pub fn type_of(&self, declarations: &impl Declarations) -> Result<Size, String> {
+        Ok(match self {
+            Expression::Int(_) | Expression::Enumerator(_, _) => Size::Int,
+            Expression::UInt(_) => Size::UInt,
+            Expression::Long(_) => Size::Long,
+            Expression::ULong(_) => Size::ULong,
+            Expression::Float(_) => Size::Float,
+            Expression::Double(_) => Size::Double,
+            Expression::String(s) => Size::Array(Box::new(Size::Byte), s.len() + 1),
+            Expression::Variable(name) => match declarations.variable(name) {
+                Some(variable) => variable.size,
+                None => return Err(format!("`{}` undeclared", name)),
+            },
+            Expression::UnaryOperator(UnOp::Dereference, e) => match e.value_type(declarations)? {
+                Size::Pointer(size, _) => *size,
+                size => return Err(format!("invalid type argument of unary `*` (`{:?}`)", size)),
+            },
+            Expression::UnaryOperator(UnOp::AddressOf, e) => {
+                Size::Pointer(Box::new(e.type_of(declarations)?), e.qualifiers(declarations)?)
+            }
+            Expression::UnaryOperator(UnOp::LogicalNegation, _) => Size::Int,
+            Expression::UnaryOperator(_, e) => e.value_type(declarations)?.promote(),
+            Expression::BinaryOperator(e1, BiOp::Addition, e2)
+            | Expression::BinaryOperator(e1, BiOp::Minus, e2) => {
+                match (e1.value_type(declarations)?, e2.value_type(declarations)?) {
+                    (Size::Pointer(_, _), Size::Pointer(_, _)) => Size::Long,
+                    (size @ Size::Pointer(_, _), _) | (_, size @ Size::Pointer(_, _)) => size,
+                    (s1, s2) => s1.common(s2),
+                }
+            }
+            Expression::BinaryOperator(e1, BiOp::Assign, _) => e1.type_of(declarations)?,
+            // The count of a shift keeps its own type
+            Expression::BinaryOperator(e1, BiOp::BitwiseShiftLeft, _)
+            | Expression::BinaryOperator(e1, BiOp::BitwiseShiftRight, _) => {
+                e1.value_type(declarations)?.promote()
+            }
+            Expression::BinaryOperator(e1, op, e2) if op.is_arithmetic() => {
+                e1.value_type(declarations)?.common(e2.value_type(declarations)?)
+            }
+            Expression::BinaryOperator(_, _, _) => Size::Int,
+            Expression::Assign(lvalue, _, _)
+            | Expression::CompoundAssign(lvalue, _, _, _)
+            | Expression::AssignPost(lvalue, _, _, _) => lvalue.type_of(declarations)?,
+            // Pointers to differently qualified types give a pointer with
+            // all their qualifiers
+            Expression::CondExp(_, body, else_) => {
+                match (body.value_type(declarations)?, else_.value_type(declarations)?) {
+                    (Size::Pointer(size, q1), Size::Pointer(_, q2)) => {
+                        Size::Pointer(size, q1.union(q2))
+                    }
+                    (size @ Size::Pointer(_, _), _) | (_, size @ Size::Pointer(_, _)) => size,
+                    (s1, s2)
+                        if (s1.is_integer() || s1.is_floating())
+                            && (s2.is_integer() || s2.is_floating()) =>
+                    {
+                        s1.common(s2)
+                    }
+                    (_, size) => size,
+                }
+            }
+            Expression::Call(name, _, _) => match declarations.function(name) {
+                Some(size) => size,
+                None => return Err(format!("implicit declaration of function `{}`", name)),
+            },
+            Expression::Cast(size, _) => size.clone(),
+            Expression::Comma(_, e2) => e2.type_of(declarations)?,
+            Expression::Member(e, name) => e.member(name, declarations)?.size,
+        })
+    }
+
+    /// Type of the value of the expression, an array being converted to a
+    /// pointer to its first element, with the qualifiers of the array.
+    pub fn value_type(&self, declarations: &impl Declarations) -> Result<Size, String> {
+        Ok(match self.type_of(declarations)? {
+            Size::Array(size, _) => Size::Pointer(size, self.qualifiers(declarations)?),
+            size => size,
+        })
+    }
+
+    /// Qualifiers of the object designated by an lvalue; a member has those
+    /// of its struct too.
+    pub fn qualifiers(&self, declarations: &impl Declarations) -> Result<Qualifiers, String> {
+        Ok(match self {
+            Expression::Variable(name) => match declarations.variable(name) {
+                Some(variable) => variable.qualifiers,
+                None => return Err(format!("`{}` undeclared", name)),
+            },
+            Expression::UnaryOperator(UnOp::Dereference, e) => match e.value_type(declarations)? {
+                Size::Pointer(_, qualifiers) => qualifiers,
+                _ => Qualifiers::default(),
+            },
+            Expression::Member(e, name) => {
+                let member = e.member(name, declarations)?;
+                e.qualifiers(declarations)?.union(member.qualifiers)
+            }
+            _ => Qualifiers::default(),
+        })
+    }
+
+    /// Member of the struct the expression evaluates to.
+    fn member(&self, name: &str, declarations: &impl Declarations) -> Result<Member, String> {
+        match self.type_of(declarations)? {
+            Size::Struct(structure) if !structure.is_complete() => {
+                Err(format!("`{:?}` is an incomplete type", structure))
+            }
+            Size::Struct(structure) => structure
+                .find_member(name)
+                .ok_or_else(|| format!("`{:?}` has no member named `{}`", structure, name)),
+            size => Err(format!(
+                "request for member `{}` in something not a struct (`{:?}`)",
+                name, size
+            )),
+        }
+    }
+
+    /// Folds an integer constant expression, `None` if it depends on the run
+    /// time. The value is held on 64 bits, extended according to its type.
+    pub fn evaluate(&self) -> Option<i64> {
+        self.evaluate_typed().map(|(value, _)| value)
+    }
+
+    /// Folds an integer constant expression along with its type, as the
+    /// generated code computes it: the operands undergo the usual arithmetic
+    /// conversions, unsigned values are divided, shifted and compared without
+    /// sign, and the result is truncated to its type.
+    fn evaluate_typed(&self) -> Option<(i64, Size)> {
+        match self {
+            Expression::Int(value) | Expression::Enumerator(_, value) => {
+                Some((*value as i64, Size::Int))
+            }
+            Expression::UInt(value) => Some((*value as i64, Size::UInt)),
+            Expression::Long(value) => Some((*value, Size::Long)),
+            Expression::ULong(value) => Some((*value as i64, Size::ULong)),
+            Expression::UnaryOperator(op, expression) => {
+                let (value, size) = expression.evaluate_typed()?;
+                let size = size.promote();
+                match op {
+                    UnOp::Negation => Some((size.truncate(value.wrapping_neg()), size)),
+                    UnOp::Bitwise => Some((size.truncate(!value), size)),
+                    UnOp::LogicalNegation => Some(((value == 0) as i64, Size::Int)),
+                    UnOp::AddressOf | UnOp::Dereference => None,
+                }
+            }
+            Expression::BinaryOperator(e1, op, e2) => {
+                let ((a, s1), (b, s2)) = (e1.evaluate_typed()?, e2.evaluate_typed()?);
+                let boolean = |value: bool| Some((value as i64, Size::Int));
+                match op {
+                    BiOp::LogicalAnd => return boolean(a != 0 && b != 0),
+                    BiOp::LogicalOr => return boolean(a != 0 || b != 0),
+                    // The count of a shift keeps its own type
+                    BiOp::BitwiseShiftLeft | BiOp::BitwiseShiftRight => {
+                        let size = s1.promote();
+                        let value = match op {
+                            BiOp::BitwiseShiftLeft => a.wrapping_shl(b as u32),
+                            _ if size.is_unsigned() => (a as u64).wrapping_shr(b as u32) as i64,
+                            _ => a.wrapping_shr(b as u32),
+                        };
+                        return Some((size.truncate(value), size));
+                    }
+                    _ => {}
+                }
+                // Pointers are only compared, without sign
+                let size = match (s1.is_pointer(), s2.is_pointer()) {
+                    (false, false) => s1.common(s2),
+                    _ if !op.is_arithmetic() => Size::ULong,
+                    _ => return None,
+                };
+                let (a, b) = (size.truncate(a), size.truncate(b));
+                let (unsigned, ua, ub) = (size.is_unsigned(), a as u64, b as u64);
+                let value = match op {
+                    BiOp::Addition => a.wrapping_add(b),
+                    BiOp::Minus => a.wrapping_sub(b),
+                    BiOp::Multiplication => a.wrapping_mul(b),
+                    BiOp::Division if unsigned => ua.checked_div(ub)? as i64,
+                    BiOp::Division => a.checked_div(b)?,
+                    BiOp::Modulus if unsigned => ua.checked_rem(ub)? as i64,
+                    BiOp::Modulus => a.checked_rem(b)?,
+                    BiOp::BitwiseAND => a & b,
+                    BiOp::BitwiseOR => a | b,
+                    BiOp::BitwiseXOR => a ^ b,
+                    BiOp::Equal => return boolean(a == b),
+                    BiOp::NotEqual => return boolean(a != b),
+                    BiOp::LessThan if unsigned => return boolean(ua < ub),
+                    BiOp::LessThan => return boolean(a < b),
+                    BiOp::LessOrEqual if unsigned => return boolean(ua <= ub),
+                    BiOp::LessOrEqual => return boolean(a <= b),
+                    BiOp::GreaterThan if unsigned => return boolean(ua > ub),
+                    BiOp::GreaterThan => return boolean(a > b),
+                    BiOp::GreaterOrEqual if unsigned => return boolean(ua >= ub),
+                    BiOp::GreaterOrEqual => return boolean(a >= b),
+                    _ => return None,
+                };
+                Some((size.truncate(value), size))
+            }
+            // Both branches give the type, the condition chooses the value
+            Expression::CondExp(condition, body, else_) => {
+                let (condition, _) = condition.evaluate_typed()?;
+                let (taken, other) = match condition {
+                    0 => (else_, body),
+                    _ => (body, else_),
+                };
+                let (value, size) = taken.evaluate_typed()?;
+                match other.evaluate_typed() {
+                    Some((_, other)) if size.is_integer() && other.is_integer() => {
+                        let size = size.common(other);
+                        Some((size.truncate(value), size))
+                    }
+                    _ => Some((value, size)),
+                }
+            }
+            Expression::Cast(Size::Float | Size::Double, _) => None,
+            Expression::Cast(size, expression) => {
+                let (value, _) = expression.evaluate_typed()?;
+                Some((size.truncate(value), size.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Folds a constant arithmetic expression as a double, the integer
+    /// subexpressions being folded with their types.
+    pub fn evaluate_floating(&self) -> Option<f64> {
+        if let Some((value, size)) = self.evaluate_typed() {
+            return Some(match size.is_unsigned() {
+                true => value as u64 as f64,
+                false => value as f64,
+            });
+        }
+        match self {
+            Expression::Float(value) => Some(*value as f64),
+            Expression::Double(value) => Some(*value),
+            Expression::UnaryOperator(UnOp::Negation, expression) => {
+                Some(-expression.evaluate_floating()?)
+            }
+            Expression::BinaryOperator(e1, op, e2) => {
+                let (a, b) = (e1.evaluate_floating()?, e2.evaluate_floating()?);
+                match op {
+                    BiOp::Addition => Some(a + b),
+                    BiOp::Minus => Some(a - b),
+                    BiOp::Multiplication => Some(a * b),
+                    BiOp::Division => Some(a / b),
+                    _ => None,
+                }
+            }
+            Expression::Cast(Size::Float, expression) => {
+                Some(expression.evaluate_floating()? as f32 as f64)
+            }
+            Expression::Cast(Size::Double, expression) => expression.evaluate_floating(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -185,3 +880,51 @@ pub enum Pair<F, S> {
     First(F),
     Second(S),
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parse;
+    use crate::tokenizer::Tokenizer;
+
+    /// Folds a constant expression of the source.
+    fn fold(text: &str) -> i64 {
+        let mut tokenizer = Tokenizer::new(format!("{};", text));
+        tokenizer.tokenize().expect("tokens");
+        Parse::new(tokenizer.tokens)
+            .parse_constant()
+            .expect("constant")
+    }
+
+    #[test]
+    fn usual_arithmetic_conversions() {
+        assert_eq!(fold("-1 < 1u"), 0);
+        assert_eq!(fold("-1 < 1"), 1);
+        assert_eq!(fold("-1 < 1l"), 1);
+        assert_eq!(fold("-1l < 1u"), 1);
+        assert_eq!(fold("-1 < 1ul"), 0);
+        assert_eq!(fold("-1 == 4294967295u"), 1);
+    }
+
+    #[test]
+    fn unsigned_division_and_shift() {
+        assert_eq!(fold("(0u - 1) / 2"), 2147483647);
+        assert_eq!(fold("(0u - 1) % 10"), 5);
+        assert_eq!(fold("-7 / 2"), -3);
+        assert_eq!(fold("-7 % 2"), -1);
+        assert_eq!(fold("(0u - 1) >> 28"), 15);
+        assert_eq!(fold("-16 >> 2"), -4);
+        assert_eq!(fold("(0ul - 1) >> 60"), 15);
+    }
+
+    #[test]
+    fn results_are_truncated_to_their_type() {
+        assert_eq!(fold("2147483647 + 1"), -2147483648);
+        assert_eq!(fold("0u - 1"), 4294967295);
+        assert_eq!(fold("~0u"), 4294967295);
+        assert_eq!(fold("-(1u)"), 4294967295);
+        assert_eq!(fold("1 << 31"), -2147483648);
+        assert_eq!(fold("(char)200"), -56);
+        assert_eq!(fold("(unsigned char)-1"), 255);
+        assert_eq!(fold("1 ? -1 : 0u"), 4294967295);
+    }
+}
@@ -1,11 +1,15 @@
 mod tokenizer;
 mod data;
 mod parser;
+mod checker;
+mod preprocessor;
 
 use std::env::args;
-use std::rc::Rc;
+use std::path::PathBuf;
 use parser::Parse;
 use crate::assembly::Generator;
+use crate::checker::Checker;
+use crate::preprocessor::Preprocessor;
 use std::fs::File;
 use std::io::Write;
 use std::process::Command;
@@ -14,24 +18,49 @@ mod assembly;
 
 fn main()  {
     let argc: Vec<String> = args().collect();
-    if argc.len() > 1 {
-        for index in 1..argc.len() {
-//            println!("File{}", argc[index]);
 
-            let mut tokenizer = tokenizer::Tokenizer::new(Rc::new(argc[index].clone()));
+    // `-I dir` or `-Idir` adds an include directory
+    let mut include_paths = vec![];
+    let mut files = vec![];
+    let mut arguments = argc.iter().skip(1);
+    while let Some(argument) = arguments.next() {
+        match argument.strip_prefix("-I") {
+            Some("") => include_paths.extend(arguments.next().map(PathBuf::from)),
+            Some(path) => include_paths.push(PathBuf::from(path)),
+            None => files.push(argument),
+        }
+    }
+
+    if !files.is_empty() {
+        for file in files {
+//            println!("File{}", file);
+
+            let mut preprocessor = Preprocessor::new(include_paths.clone());
+            let source = match preprocessor.preprocess(file) {
+                Ok(source) => source,
+                Err(error) => {
+                    println!("[ERROR] in `{}` :\t{} : L:{}:{}", preprocessor.file(), error.error, error.line, error.position);
+                    continue;
+                }
+            };
+            let mut tokenizer = tokenizer::Tokenizer::new(source);
 //            println!("Tokenizer");
-            tokenizer.tokenize();
-            println!("Parser : {:?}", tokenizer.tokens);
-            let mut parser = Parse::new(tokenizer.tokens);
-            match parser.parse() {
+            let program = tokenizer
+                .tokenize()
+                .and_then(|_| {
+                    println!("Parser : {:?}", tokenizer.tokens);
+                    Parse::new(tokenizer.tokens).parse()
+                })
+                .and_then(|program| Checker::new().check(&program).map(|_| program));
+            match program {
                 Ok(program) => {
                     let mut generator = Generator::new();
                     println!("Generator : {:#?}", program);
                     let asm_generate = generator.generate(&program);
-                    let asm_file = argc[index].replace(".c", ".s");
+                    let asm_file = file.replace(".c", ".s");
                     let mut f = File::create(&asm_file).expect("error create file");
-                    f.write(asm_generate.as_bytes()).expect("error write file");
-                    let exec_file = argc[index].replace(".c", "");
+                    f.write_all(asm_generate.as_bytes()).expect("error write file");
+                    let exec_file = file.replace(".c", "");
 
                     let c = Command::new("gcc")
                         .arg("-g")
@@ -49,9 +78,9 @@ fn main()  {
 
                 },
                 Err(error) => {
-                    let _array: Vec<String> = tokenizer.file.split('\n').map(|s| s.to_string()).collect();
-
-                    println!("[ERROR] in `{}` :\t{} : L:{}:{}", argc[index], error.error, error.line, error.position);
+                    // Lines of the preprocessed source are mapped back to the original files
+                    let (file, line) = preprocessor.locate(error.line);
+                    println!("[ERROR] in `{}` :\t{} : L:{}:{}", file, error.error, line, error.position);
                 },
             };
 
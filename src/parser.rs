@@ -1,15 +1,58 @@
 use crate::data::Declare::Declare;
 use crate::data::Expression::{BinaryOperator, UnaryOperator};
 use crate::data::Pair::{First, Second};
-use crate::data::{BiOp, Compound, Expression, Function, Program, Statement, Variable};
+use crate::data::{
+    BiOp, Compound, Declarations, Expression, Function, Initializer, Layout, Location, Program,
+    Qualifiers, Size, Statement, Storage, Struct, UnOp, Variable,
+};
 use crate::tokenizer::TokenType::*;
 use crate::tokenizer::{Keyword, Token, TokenType, Value};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::iter::Peekable;
+use std::rc::Rc;
 use std::vec::IntoIter;
 
 pub struct Parse {
     iter: Peekable<IntoIter<Token>>,
     stack: Vec<Token>,
+    /// Struct and union types by tag, a tag being usable before its
+    /// definition.
+    structs: HashMap<String, Rc<Struct>>,
+    /// Tags of the defined enumerations.
+    enums: HashSet<String>,
+    /// Values of the enumeration constants.
+    enumerators: HashMap<String, i32>,
+    /// Ordinary identifiers of the enclosing scopes, the innermost last. An
+    /// object declared in an inner scope hides a typedef name.
+    names: Vec<HashMap<String, Name>>,
+}
+
+/// What an ordinary identifier declares.
+#[derive(Debug, Clone, PartialEq)]
+enum Name {
+    Typedef(Size, Qualifiers),
+    /// Object of the type, with whether it has static storage.
+    Object(Size, bool),
+    /// Function with its return type.
+    Function(Size),
+}
+
+/// The parser types the operands of `sizeof`.
+impl Declarations for Parse {
+    fn variable(&self, name: &str) -> Option<Variable> {
+        match self.name(name) {
+            Some(Name::Object(size, _)) => Some(Variable::new(name.to_string(), size.clone())),
+            _ => None,
+        }
+    }
+
+    fn function(&self, name: &str) -> Option<Size> {
+        match self.name(name) {
+            Some(Name::Function(size)) => Some(size.clone()),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -28,6 +71,14 @@ impl ParseError {
         }
     }
 
+    pub fn new_with_location(error: String, location: Location) -> ParseError {
+        ParseError {
+            error,
+            position: location.position,
+            line: location.line,
+        }
+    }
+
     pub fn new(error: String, position: usize, line: usize) -> ParseError {
         ParseError {
             error,
@@ -52,6 +103,10 @@ impl Parse {
         Parse {
             iter: tokens.into_iter().peekable(),
             stack: vec![],
+            structs: HashMap::new(),
+            enums: HashSet::new(),
+            enumerators: HashMap::new(),
+            names: vec![HashMap::new()],
         }
     }
 
@@ -61,7 +116,7 @@ impl Parse {
         } else if let Some(token) = self.iter.peek() {
             Ok(token.token.clone())
         } else {
-            Err(ParseError::new(format!(""), 2, 4))
+            Err(ParseError::new(String::new(), 2, 4))
         }
     }
 
@@ -75,7 +130,7 @@ impl Parse {
                         "_!_ bad token match {:?} and shall be {:?}",
                         peek.token, token
                     ),
-                    &peek,
+                    peek,
                 ))
             }
         } else {
@@ -89,7 +144,7 @@ impl Parse {
         } else if let Some(e) = self.iter.next() {
             Ok(e.clone())
         } else {
-            Err(ParseError::new(format!("Missing Token"), 0, 0))
+            Err(ParseError::new("Missing Token".to_string(), 0, 0))
         }
     }
 
@@ -143,6 +198,430 @@ impl Parse {
         }
     }
 
+    /// Location of the next token, used to tag the nodes checked after parsing.
+    fn location(&mut self) -> Location {
+        let token = match self.stack.last() {
+            Some(token) => Some(token),
+            None => self.iter.peek(),
+        };
+        token.map_or_else(Location::default, Location::from)
+    }
+
+    fn is_type(&self, token: &TokenType) -> bool {
+        match token {
+            TokenType::Keyword(Keyword::Int)
+            | TokenType::Keyword(Keyword::Char)
+            | TokenType::Keyword(Keyword::Float)
+            | TokenType::Keyword(Keyword::Double)
+            | TokenType::Keyword(Keyword::Void)
+            | TokenType::Keyword(Keyword::Short)
+            | TokenType::Keyword(Keyword::Long)
+            | TokenType::Keyword(Keyword::Signed)
+            | TokenType::Keyword(Keyword::Unsigned)
+            | TokenType::Keyword(Keyword::Struct)
+            | TokenType::Keyword(Keyword::Union)
+            | TokenType::Keyword(Keyword::Enum)
+            | TokenType::Keyword(Keyword::Const)
+            | TokenType::Keyword(Keyword::Volatile) => true,
+            TokenType::Identifier(name) => self.typedef(name).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Declaration of an ordinary identifier visible from the current scope.
+    fn name(&self, name: &str) -> Option<&Name> {
+        self.names.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Type named by a typedef visible from the current scope.
+    fn typedef(&self, name: &str) -> Option<(Size, Qualifiers)> {
+        match self.name(name) {
+            Some(Name::Typedef(size, qualifiers)) => Some((size.clone(), *qualifiers)),
+            _ => None,
+        }
+    }
+
+    fn enter_scope(&mut self) {
+        self.names.push(HashMap::new());
+    }
+
+    fn leave_scope(&mut self) {
+        self.names.pop();
+    }
+
+    /// Declares an ordinary identifier in the current scope. A typedef may
+    /// only be repeated with the same type.
+    fn declare(
+        &mut self,
+        name: &str,
+        declared: Name,
+        location: Location,
+    ) -> Result<(), ParseError> {
+        let scope = self.names.last_mut().expect("no scope");
+        match (scope.get(name), &declared) {
+            (Some(previous @ Name::Typedef(_, _)), Name::Typedef(_, _))
+                if *previous != declared =>
+            {
+                Err(ParseError::new_with_location(
+                    format!("conflicting types for typedef `{}`", name),
+                    location,
+                ))
+            }
+            (Some(Name::Typedef(_, _)), Name::Object(_, _) | Name::Function(_))
+            | (Some(Name::Object(_, _) | Name::Function(_)), Name::Typedef(_, _)) => {
+                Err(ParseError::new_with_location(
+                    format!("`{}` redeclared as a different kind of symbol", name),
+                    location,
+                ))
+            }
+            _ => {
+                scope.insert(name.to_string(), declared);
+                Ok(())
+            }
+        }
+    }
+
+    /// Storage class specifier starting a declaration, if any.
+    fn parse_storage(&mut self) -> Result<Option<Storage>, ParseError> {
+        let storage = match self.peek()? {
+            TokenType::Keyword(Keyword::Static) => Storage::Static,
+            TokenType::Keyword(Keyword::Extern) => Storage::Extern,
+            _ => return Ok(None),
+        };
+        self.next_token()?;
+        Ok(Some(storage))
+    }
+
+    /// `typedef type declarator;`, the keyword being the next token.
+    fn parse_typedef(&mut self) -> Result<(), ParseError> {
+        self.next_token()?;
+        let (size, qualifiers) = self.parse_type()?;
+        let location = self.location();
+        let variable = self.parse_declarator(size, qualifiers, true)?;
+        self.match_token(Semicolon)?;
+        self.declare(
+            &variable.name,
+            Name::Typedef(variable.size, variable.qualifiers),
+            location,
+        )
+    }
+
+    /// `const` and `volatile`, in any order.
+    fn parse_qualifiers(&mut self) -> Result<Qualifiers, ParseError> {
+        let mut qualifiers = Qualifiers::default();
+        loop {
+            match self.peek()? {
+                TokenType::Keyword(Keyword::Const) => qualifiers.constant = true,
+                TokenType::Keyword(Keyword::Volatile) => qualifiers.volatile = true,
+                _ => return Ok(qualifiers),
+            }
+            self.next_token()?;
+        }
+    }
+
+    /// Type specifiers with the qualifiers around them, as in `const int` or
+    /// `char const`.
+    fn parse_type(&mut self) -> Result<(Size, Qualifiers), ParseError> {
+        let mut qualifiers = self.parse_qualifiers()?;
+        let size = match self.peek()? {
+            TokenType::Identifier(name) if self.typedef(&name).is_some() => {
+                self.next_token()?;
+                let (size, named) = self.typedef(&name).expect("typedef name");
+                qualifiers = qualifiers.union(named);
+                size
+            }
+            _ => self.parse_specifiers()?,
+        };
+        Ok((size, qualifiers.union(self.parse_qualifiers()?)))
+    }
+
+    fn parse_specifiers(&mut self) -> Result<Size, ParseError> {
+        let token = self.next_token()?;
+        match token.token {
+            TokenType::Keyword(Keyword::Struct) => self.parse_struct(false),
+            TokenType::Keyword(Keyword::Union) => self.parse_struct(true),
+            TokenType::Keyword(Keyword::Enum) => self.parse_enum(),
+            TokenType::Keyword(Keyword::Float) => Ok(Size::Float),
+            TokenType::Keyword(Keyword::Double) => Ok(Size::Double),
+            TokenType::Keyword(Keyword::Void) => Ok(Size::Void),
+            TokenType::Keyword(keyword) if self.is_type(&token.token) => {
+                self.parse_integer_type(keyword, Location::from(&token))
+            }
+            token_type => Err(ParseError::new(
+                format!("_!_ bad token match {:?} and shall be a type", token_type),
+                token.position,
+                token.line,
+            )),
+        }
+    }
+
+    /// Integer type specifiers, which may come in any order: `unsigned`,
+    /// `long long int`, `short unsigned`... The first one is already consumed.
+    fn parse_integer_type(
+        &mut self,
+        keyword: Keyword,
+        location: Location,
+    ) -> Result<Size, ParseError> {
+        let mut keywords = vec![keyword];
+        while let TokenType::Keyword(
+            keyword @ (Keyword::Int
+            | Keyword::Char
+            | Keyword::Short
+            | Keyword::Long
+            | Keyword::Signed
+            | Keyword::Unsigned),
+        ) = self.peek()?
+        {
+            self.next_token()?;
+            keywords.push(keyword);
+        }
+        let count = |keyword: Keyword| keywords.iter().filter(|k| **k == keyword).count();
+        let (int, char, short, long) = (
+            count(Keyword::Int),
+            count(Keyword::Char),
+            count(Keyword::Short),
+            count(Keyword::Long),
+        );
+        let (signed, unsigned) = (count(Keyword::Signed), count(Keyword::Unsigned));
+
+        let valid = int <= 1
+            && signed + unsigned <= 1
+            && match (char, short, long) {
+                (0, 0, 0) => true,
+                (1, 0, 0) => int == 0,
+                (0, 1, 0) | (0, 0, 1) | (0, 0, 2) => true,
+                _ => false,
+            };
+        if !valid {
+            return Err(ParseError::new_with_location(
+                format!("invalid combination of type specifiers {:?}", keywords),
+                location,
+            ));
+        }
+        Ok(match (char, short, long, unsigned) {
+            (1, _, _, 0) => Size::Byte,
+            (1, _, _, _) => Size::UByte,
+            (_, 1, _, 0) => Size::Short,
+            (_, 1, _, _) => Size::UShort,
+            (_, _, 0, 0) => Size::Int,
+            (_, _, 0, _) => Size::UInt,
+            (_, _, _, 0) => Size::Long,
+            _ => Size::ULong,
+        })
+    }
+
+    /// `struct tag`, `struct tag { members }` or `struct { members }`, and
+    /// the same for `union`, the keyword being already consumed.
+    fn parse_struct(&mut self, union: bool) -> Result<Size, ParseError> {
+        let location = self.location();
+        let tag = match self.peek()? {
+            Identifier(_) => Some(self.match_identifier()?),
+            _ => None,
+        };
+        let structure = match tag {
+            Some(tag) => {
+                if self.enums.contains(&tag) {
+                    return Err(Self::wrong_tag(&tag, location));
+                }
+                self.structs
+                    .entry(tag.clone())
+                    .or_insert_with(|| Rc::new(Struct::new(tag, union)))
+                    .clone()
+            }
+            None if self.peek()? == OpenBrace => {
+                Rc::new(Struct::new("<anonymous>".to_string(), union))
+            }
+            None => {
+                return Err(ParseError::new_with_location(
+                    "expected a tag or a definition".to_string(),
+                    location,
+                ))
+            }
+        };
+        if structure.union != union {
+            return Err(Self::wrong_tag(&structure.tag, location));
+        }
+        if self.peek()? != OpenBrace {
+            return Ok(Size::Struct(structure));
+        }
+        if structure.is_complete() {
+            return Err(ParseError::new_with_location(
+                format!("redefinition of `{:?}`", structure),
+                location,
+            ));
+        }
+
+        self.next_token()?;
+        let mut members: Vec<Variable> = vec![];
+        while self.peek()? != CloseBrace {
+            let (size, qualifiers) = self.parse_type()?;
+            let location = self.location();
+            let member = self.parse_object_declarator(size, qualifiers)?;
+            if members.iter().any(|m| m.name == member.name) {
+                return Err(ParseError::new_with_location(
+                    format!("duplicate member `{}`", member.name),
+                    location,
+                ));
+            }
+            members.push(member);
+            self.match_token(Semicolon)?;
+        }
+        self.next_token()?;
+
+        structure.define(Layout::new(members, union));
+        Ok(Size::Struct(structure))
+    }
+
+    /// `enum tag`, `enum tag { enumerators }` or `enum { enumerators }`, the
+    /// keyword being already consumed. Enumerators count from the previous
+    /// value, or from 0.
+    fn parse_enum(&mut self) -> Result<Size, ParseError> {
+        let location = self.location();
+        let tag = match self.peek()? {
+            Identifier(_) => Some(self.match_identifier()?),
+            _ => None,
+        };
+        if let Some(tag) = &tag {
+            if self.structs.contains_key(tag) {
+                return Err(Self::wrong_tag(tag, location));
+            }
+        }
+        if self.peek()? != OpenBrace {
+            return match tag {
+                Some(_) => Ok(Size::Int),
+                None => Err(ParseError::new_with_location(
+                    "expected a tag or a definition".to_string(),
+                    location,
+                )),
+            };
+        }
+        if let Some(tag) = tag {
+            if !self.enums.insert(tag.clone()) {
+                return Err(ParseError::new_with_location(
+                    format!("redefinition of `enum {}`", tag),
+                    location,
+                ));
+            }
+        }
+
+        self.next_token()?;
+        let mut value = 0;
+        while self.peek()? != CloseBrace {
+            let location = self.location();
+            let name = self.match_identifier()?;
+            if self.peek()? == Assignement {
+                self.next_token()?;
+                value = self
+                    .parse_ternary_condition()?
+                    .evaluate()
+                    .and_then(|value| i32::try_from(value).ok())
+                    .ok_or_else(|| {
+                        ParseError::new_with_location(
+                            format!("value of enumerator `{}` is not an int constant", name),
+                            location,
+                        )
+                    })?;
+            }
+            if self.enumerators.insert(name.clone(), value).is_some() {
+                return Err(ParseError::new_with_location(
+                    format!("redefinition of enumerator `{}`", name),
+                    location,
+                ));
+            }
+            value = value.wrapping_add(1);
+            if self.peek()? != Comma {
+                break;
+            }
+            self.next_token()?;
+        }
+        self.match_token(CloseBrace)?;
+        Ok(Size::Int)
+    }
+
+    fn wrong_tag(tag: &str, location: Location) -> ParseError {
+        ParseError::new_with_location(
+            format!("`{}` defined as the wrong kind of tag", tag),
+            location,
+        )
+    }
+
+    /// Pointer levels followed by the declared name and array lengths. The
+    /// name is optional in an abstract declarator, such as a parameter of a
+    /// prototype. An array whose length is omitted gets a length of 0. The
+    /// qualifiers after a `*` are those of the pointer itself.
+    fn parse_declarator(
+        &mut self,
+        mut size: Size,
+        mut qualifiers: Qualifiers,
+        named: bool,
+    ) -> Result<Variable, ParseError> {
+        while self.peek()? == Multiplication {
+            self.next_token()?;
+            size = Size::Pointer(Box::new(size), qualifiers);
+            qualifiers = self.parse_qualifiers()?;
+        }
+        let name = match self.peek()? {
+            Comma | CloseParentheses | OpenBracket if !named => String::new(),
+            _ => self.match_identifier()?,
+        };
+
+        let mut lengths = vec![];
+        while self.peek()? == OpenBracket {
+            let location = self.location();
+            self.next_token()?;
+            if self.peek()? == CloseBracket && lengths.is_empty() {
+                lengths.push(0);
+            } else {
+                let length = self
+                    .parse_ternary_condition()?
+                    .evaluate()
+                    .filter(|length| *length > 0)
+                    .ok_or_else(|| {
+                        ParseError::new_with_location(
+                            format!("size of array `{}` is not a positive constant", name),
+                            location,
+                        )
+                    })?;
+                lengths.push(length as usize);
+            }
+            self.match_token(CloseBracket)?;
+        }
+        // `a[2][3]` is an array of 2 arrays of 3 elements
+        for length in lengths.into_iter().rev() {
+            size = Size::Array(Box::new(size), length);
+        }
+        Ok(Variable {
+            name,
+            size,
+            qualifiers,
+        })
+    }
+
+    /// Declarator of an object, which must have a complete type. The length
+    /// of an array may be left to its initializer. A function declarator,
+    /// followed by its parameters, may return `void`.
+    fn parse_object_declarator(
+        &mut self,
+        size: Size,
+        qualifiers: Qualifiers,
+    ) -> Result<Variable, ParseError> {
+        let location = self.location();
+        let variable = self.parse_declarator(size, qualifiers, true)?;
+        if matches!(variable.size, Size::Array(_, 0)) && self.peek()? != Assignement {
+            return Err(ParseError::new_with_location(
+                format!("array size missing in `{}`", variable.name),
+                location,
+            ));
+        }
+        if !variable.size.is_complete() && self.peek()? != OpenParentheses {
+            return Err(ParseError::new_with_location(
+                format!("`{}` has an incomplete type", variable.name),
+                location,
+            ));
+        }
+        Ok(variable)
+    }
+
     fn has_next(&mut self) -> bool {
         self.iter.peek().is_some()
     }
@@ -151,24 +630,480 @@ impl Parse {
 impl Parse {
     pub fn parse(&mut self) -> Result<Program, ParseError> {
         let mut functions: Vec<Function> = vec![];
-        let globals: Vec<Variable> = vec![];
+        let mut globals: Vec<crate::data::Declare> = vec![];
 
-        while self.has_next() && self.next_is(TokenType::Keyword(Keyword::Int))? {
-            functions.push(self.parse_function()?);
+        while self.has_next() {
+            if self.peek()? == TokenType::Keyword(Keyword::Typedef) {
+                self.parse_typedef()?;
+                continue;
+            }
+            let storage = self.parse_storage()?;
+            let (size, qualifiers) = self.parse_type()?;
+            // Declaration of a struct alone
+            if self.peek()? == Semicolon {
+                self.next_token()?;
+                continue;
+            }
+            let location = self.location();
+            let variable = self.parse_object_declarator(size.clone(), qualifiers)?;
+            let declared = match self.peek()? {
+                OpenParentheses => Name::Function(variable.size.clone()),
+                _ => Name::Object(variable.size.clone(), true),
+            };
+            self.declare(&variable.name, declared, location)?;
+
+            if self.peek()? == OpenParentheses {
+                functions.push(self.parse_function(
+                    variable.name,
+                    variable.size,
+                    storage,
+                    location,
+                )?);
+            } else {
+                globals.push(self.parse_global(storage, size, qualifiers, variable, location)?);
+            }
         }
 
         Ok(Program { functions, globals })
     }
 
-    fn parse_function(&mut self) -> Result<Function, ParseError> {
+    /// Constant expression followed by a `;`, as the condition of an `#if`.
+    pub fn parse_constant(&mut self) -> Result<i64, ParseError> {
+        let location = self.location();
+        let value = self.parse_ternary_condition()?.evaluate().ok_or_else(|| {
+            ParseError::new_with_location("expression is not a constant".to_string(), location)
+        })?;
+        let location = self.location();
+        self.match_token(Semicolon)?;
+        if self.has_next() {
+            return Err(ParseError::new_with_location(
+                "unexpected token after the expression".to_string(),
+                location,
+            ));
+        }
+        Ok(value)
+    }
+
+    /// File-scope variables of the type `size`, whose initializers must be
+    /// constant expressions. The first declarator is already parsed.
+    fn parse_global(
+        &mut self,
+        storage: Option<Storage>,
+        size: Size,
+        qualifiers: Qualifiers,
+        variable: Variable,
+        location: Location,
+    ) -> Result<crate::data::Declare, ParseError> {
+        let mut declarators = vec![self.parse_global_initializer(variable, location)?];
+        while self.peek()? == Comma {
+            self.next_token()?;
+            let location = self.location();
+            let variable = self.parse_object_declarator(size.clone(), qualifiers)?;
+            self.declare(&variable.name, Name::Object(variable.size.clone(), true), location)?;
+            declarators.push(self.parse_global_initializer(variable, location)?);
+        }
+        self.match_token(Semicolon)?;
+        Ok(Declare(storage, declarators))
+    }
+
+    fn parse_global_initializer(
+        &mut self,
+        mut variable: Variable,
+        declarator: Location,
+    ) -> Result<(Variable, Option<Initializer>, Location), ParseError> {
+        if self.peek()? == Assignement {
+            self.next_token()?;
+            let location = self.location();
+            let initializer = self.parse_initializer(&mut variable)?;
+            // The initializer may have completed the type
+            let declared = Name::Object(variable.size.clone(), true);
+            self.declare(&variable.name, declared, declarator)?;
+            let initializer = self.fold_constants(&variable, initializer, location)?;
+            Ok((variable, Some(initializer), declarator))
+        } else {
+            Ok((variable, None, declarator))
+        }
+    }
+
+    /// Initializer of an object of static storage duration, which is
+    /// emitted as data.
+    fn fold_constants(
+        &self,
+        variable: &Variable,
+        initializer: Initializer,
+        location: Location,
+    ) -> Result<Initializer, ParseError> {
+        Ok(match initializer {
+            Initializer::Expression(expression) => Initializer::Expression(
+                self.fold_initializer(variable, &variable.size, expression, location)?,
+            ),
+            Initializer::List(entries) => Initializer::List(
+                entries
+                    .into_iter()
+                    .map(|(offset, size, expression)| {
+                        let value = self.fold_initializer(variable, &size, expression, location)?;
+                        Ok((offset, size, value))
+                    })
+                    .collect::<Result<_, ParseError>>()?,
+            ),
+        })
+    }
+
+    /// Constant value of a scalar of type `size` initializing a global. A
+    /// pointer may also be initialized by an address constant, left to the
+    /// generator which knows the symbols.
+    fn fold_initializer(
+        &self,
+        variable: &Variable,
+        size: &Size,
+        expression: Expression,
+        location: Location,
+    ) -> Result<Expression, ParseError> {
+        let value = if size.is_floating() {
+            expression.evaluate_floating().map(Expression::Double)
+        } else if let Some(value) = expression.evaluate() {
+            Some(Expression::Long(value))
+        } else if size.is_pointer() && self.is_address_constant(&expression) {
+            Some(expression)
+        } else {
+            None
+        };
+        value.ok_or_else(|| {
+            ParseError::new_with_location(
+                format!("initializer of `{}` is not a constant", variable.name),
+                location,
+            )
+        })
+    }
+
+    /// Whether the expression is an address constant: a string literal or
+    /// the address of an object of static storage, moved by an integer
+    /// constant.
+    fn is_address_constant(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::String(_) => true,
+            // An array is converted to the address of its first element
+            Expression::Variable(name) => {
+                matches!(self.name(name), Some(Name::Object(Size::Array(_, _), true)))
+            }
+            Expression::UnaryOperator(UnOp::AddressOf, lvalue) => self.is_static_object(lvalue),
+            Expression::BinaryOperator(e1, BiOp::Addition, e2) => {
+                (self.is_address_constant(e1) && e2.evaluate().is_some())
+                    || (e1.evaluate().is_some() && self.is_address_constant(e2))
+            }
+            Expression::BinaryOperator(e1, BiOp::Minus, e2) => {
+                self.is_address_constant(e1) && e2.evaluate().is_some()
+            }
+            Expression::Cast(size, expression) if size.is_pointer() => {
+                self.is_address_constant(expression)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether an lvalue designates an object of static storage, or a part
+    /// of one at a constant offset.
+    fn is_static_object(&self, lvalue: &Expression) -> bool {
+        match lvalue {
+            Expression::Variable(name) => matches!(self.name(name), Some(Name::Object(_, true))),
+            Expression::Member(expression, _) => self.is_static_object(expression),
+            Expression::UnaryOperator(UnOp::Dereference, expression) => {
+                self.is_address_constant(expression)
+            }
+            _ => false,
+        }
+    }
+
+    /// Initializer following the `=` of a declarator. An aggregate takes a
+    /// brace-enclosed list, or a string literal for an array of characters;
+    /// an omitted array length is the number of elements initialized.
+    fn parse_initializer(&mut self, variable: &mut Variable) -> Result<Initializer, ParseError> {
+        let location = self.location();
+        if self.peek()? != OpenBrace && !self.is_string_initializer(&variable.size)? {
+            if let Size::Array(_, _) = variable.size {
+                return Err(ParseError::new_with_location(
+                    format!("invalid initializer for array `{}`", variable.name),
+                    location,
+                ));
+            }
+            return Ok(Initializer::Expression(self.parse_assignement()?));
+        }
+        let mut entries = vec![];
+        let length = self.parse_sub_initializer(&variable.size, 0, &mut entries)?;
+        if let Size::Array(element, 0) = &variable.size {
+            if length == 0 {
+                return Err(ParseError::new_with_location(
+                    format!("array size missing in `{}`", variable.name),
+                    location,
+                ));
+            }
+            variable.size = Size::Array(element.clone(), length);
+        }
+        Ok(Initializer::List(entries))
+    }
+
+    /// Initializer of the object of type `size` at `offset` in the declared
+    /// one. The braces around an aggregate may be omitted, its members then
+    /// taking the next initializers of the enclosing list. Returns the number
+    /// of elements initialized.
+    fn parse_sub_initializer(
+        &mut self,
+        size: &Size,
+        offset: i32,
+        entries: &mut Vec<(i32, Size, Expression)>,
+    ) -> Result<usize, ParseError> {
+        if self.is_string_initializer(size)? {
+            return self.parse_string_initializer(size, offset, entries);
+        }
+        let braced = self.peek()? == OpenBrace;
+        if braced {
+            self.next_token()?;
+        }
+        let length = if size.is_aggregate() {
+            self.parse_initializer_list(size, offset, entries, braced)?
+        } else if braced {
+            self.parse_sub_initializer(size, offset, entries)?
+        } else {
+            let expression = self.parse_assignement()?;
+            Self::initialize(entries, offset, size.clone(), expression);
+            1
+        };
+        if braced {
+            if self.peek()? == Comma {
+                self.next_token()?;
+            }
+            self.match_token(CloseBrace)?;
+        }
+        Ok(length)
+    }
+
+    /// Members of an aggregate, in order from the last designated one. A list
+    /// without braces stops once the aggregate is full, or at a designator,
+    /// which belongs to the enclosing list.
+    fn parse_initializer_list(
+        &mut self,
+        size: &Size,
+        offset: i32,
+        entries: &mut Vec<(i32, Size, Expression)>,
+        braced: bool,
+    ) -> Result<usize, ParseError> {
+        let (mut index, mut length) = (0, 0);
+        loop {
+            let designated = match self.peek()? {
+                CloseBrace => break,
+                Dot | OpenBracket if !braced => break,
+                Dot | OpenBracket => {
+                    index = self.parse_designator(size)?;
+                    true
+                }
+                _ if Self::is_full(size, index) && braced => {
+                    return Err(ParseError::new_with_location(
+                        "excess elements in initializer".to_string(),
+                        self.location(),
+                    ))
+                }
+                _ if Self::is_full(size, index) => break,
+                _ => false,
+            };
+            let (member_size, member_offset) = Self::sub_object(size, index);
+            if designated {
+                self.parse_designation(&member_size, offset + member_offset, entries)?;
+            } else {
+                self.parse_sub_initializer(&member_size, offset + member_offset, entries)?;
+            }
+            index += 1;
+            length = length.max(index);
+            if (!braced && Self::is_full(size, index)) || self.peek()? != Comma {
+                break;
+            }
+            let comma = self.next_token()?;
+            if !braced && matches!(self.peek()?, CloseBrace | Dot | OpenBracket) {
+                self.push(comma);
+                break;
+            }
+        }
+        Ok(length)
+    }
+
+    /// Rest of a designation such as `[1].x = 2`, the following designators
+    /// selecting inside the object already designated.
+    fn parse_designation(
+        &mut self,
+        size: &Size,
+        offset: i32,
+        entries: &mut Vec<(i32, Size, Expression)>,
+    ) -> Result<(), ParseError> {
+        if matches!(self.peek()?, Dot | OpenBracket) {
+            let index = self.parse_designator(size)?;
+            let (member_size, member_offset) = Self::sub_object(size, index);
+            self.parse_designation(&member_size, offset + member_offset, entries)
+        } else {
+            self.match_token(Assignement)?;
+            self.parse_sub_initializer(size, offset, entries)?;
+            Ok(())
+        }
+    }
+
+    /// Index of the element or member selected by `[index]` or `.member`.
+    fn parse_designator(&mut self, size: &Size) -> Result<usize, ParseError> {
+        let location = self.location();
+        match (self.next_token()?.token, size) {
+            (OpenBracket, Size::Array(_, length)) => {
+                let index = self
+                    .parse_ternary_condition()?
+                    .evaluate()
+                    .filter(|index| *index >= 0 && (*length == 0 || (*index as usize) < *length))
+                    .ok_or_else(|| {
+                        ParseError::new_with_location(
+                            "array index in initializer is not a constant within bounds"
+                                .to_string(),
+                            location,
+                        )
+                    })?;
+                self.match_token(CloseBracket)?;
+                Ok(index as usize)
+            }
+            (Dot, Size::Struct(s)) => {
+                let name = self.match_identifier()?;
+                s.layout()
+                    .members
+                    .iter()
+                    .position(|member| member.name == name)
+                    .ok_or_else(|| {
+                        ParseError::new_with_location(
+                            format!("`{:?}` has no member named `{}`", s, name),
+                            location,
+                        )
+                    })
+            }
+            _ => Err(ParseError::new_with_location(
+                format!("designator does not match the type `{:?}`", size),
+                location,
+            )),
+        }
+    }
+
+    /// Whether a string literal initializes an array of characters.
+    fn is_string_initializer(&mut self, size: &Size) -> Result<bool, ParseError> {
+        Ok(
+            matches!(size, Size::Array(element, _) if matches!(**element, Size::Byte | Size::UByte))
+                && matches!(self.peek()?, Literal(Value::String(_))),
+        )
+    }
+
+    /// Characters of a string literal, with its null byte if the array has
+    /// room for it.
+    fn parse_string_initializer(
+        &mut self,
+        size: &Size,
+        offset: i32,
+        entries: &mut Vec<(i32, Size, Expression)>,
+    ) -> Result<usize, ParseError> {
+        let location = self.location();
+        let mut string = match self.next_token()?.token {
+            Literal(Value::String(string)) => string,
+            token => panic!("expected a string literal, found {:?}", token),
+        };
+        string.push(0);
+        if let Size::Array(element, length) = size {
+            if *length != 0 {
+                if string.len() > length + 1 {
+                    return Err(ParseError::new_with_location(
+                        "initializer-string for array is too long".to_string(),
+                        location,
+                    ));
+                }
+                string.truncate(*length);
+            }
+            for (index, c) in string.iter().enumerate() {
+                let expression = Expression::Int(*c as i32);
+                Self::initialize(entries, offset + index as i32, *element.clone(), expression);
+            }
+        }
+        Ok(string.len())
+    }
+
+    /// Whether every element or member of an aggregate is initialized from
+    /// `index`; a union takes a single one.
+    fn is_full(size: &Size, index: usize) -> bool {
+        match size {
+            Size::Array(_, 0) => false,
+            Size::Array(_, length) => index >= *length,
+            Size::Struct(s) if s.union => index >= 1,
+            Size::Struct(s) => index >= s.layout().members.len(),
+            _ => index >= 1,
+        }
+    }
+
+    /// Type and offset of the element or member `index` of an aggregate.
+    fn sub_object(size: &Size, index: usize) -> (Size, i32) {
+        match size {
+            Size::Array(element, _) => {
+                (*element.clone(), i32::from(element.as_ref()) * index as i32)
+            }
+            Size::Struct(s) => {
+                let member = &s.layout().members[index];
+                (member.size.clone(), member.offset)
+            }
+            size => panic!("`{:?}` is not an aggregate", size),
+        }
+    }
+
+    /// A later initializer of the same scalar overrides the earlier one.
+    fn initialize(
+        entries: &mut Vec<(i32, Size, Expression)>,
+        offset: i32,
+        size: Size,
+        expression: Expression,
+    ) {
+        entries.retain(|(o, _, _)| *o != offset);
+        entries.push((offset, size, expression));
+    }
+
+    fn parse_function(
+        &mut self,
+        name: String,
+        size: Size,
+        storage: Option<Storage>,
+        location: Location,
+    ) -> Result<Function, ParseError> {
         //println!("parse_function");
-        self.match_token(TokenType::Keyword(Keyword::Int))?;
-        let name = self.match_identifier()?;
         self.match_token(TokenType::OpenParentheses)?;
 
-        let variables: Vec<Variable> = vec![];
+        // The parameters are in the scope of the body
+        self.enter_scope();
+        let (variables, variadic) = self.parse_parameters()?;
 
         self.match_token(TokenType::CloseParentheses)?;
+
+        let by_value = std::iter::once(&size)
+            .chain(variables.iter().map(|v| &v.size))
+            .find_map(|size| match size {
+                Size::Struct(structure) => Some(structure.clone()),
+                _ => None,
+            });
+        if let Some(structure) = by_value {
+            return Err(ParseError::new_with_location(
+                format!(
+                    "`{}` passes or returns `{:?}` by value, which is not supported",
+                    name, structure
+                ),
+                location,
+            ));
+        }
+        if self.peek()? == Semicolon {
+            self.next_token()?;
+            self.leave_scope();
+            return Ok(Function {
+                name,
+                size,
+                variables,
+                variadic,
+                compounds: None,
+                storage,
+                location,
+            });
+        }
         self.match_token(TokenType::OpenBrace)?;
 
         let mut compounds: Vec<Compound> = vec![];
@@ -177,43 +1112,127 @@ impl Parse {
             compounds.push(self.parse_compound()?);
         }
         self.match_token(TokenType::CloseBrace)?;
+        self.leave_scope();
 
         Ok(Function {
             name,
+            size,
             variables,
-            compounds,
+            variadic,
+            compounds: Some(compounds),
+            storage,
+            location,
         })
     }
 
+    /// Parameters of a function, with whether they end with `...`.
+    fn parse_parameters(&mut self) -> Result<(Vec<Variable>, bool), ParseError> {
+        let mut variables: Vec<Variable> = vec![];
+
+        if self.peek()? == CloseParentheses {
+            return Ok((variables, false));
+        }
+        loop {
+            if self.peek()? == Ellipsis {
+                if variables.is_empty() {
+                    return Err(ParseError::new_with_location(
+                        "a named parameter is required before `...`".to_string(),
+                        self.location(),
+                    ));
+                }
+                self.next_token()?;
+                return Ok((variables, true));
+            }
+            let (size, qualifiers) = self.parse_type()?;
+            // `(void)` declares no parameter
+            if size == Size::Void && variables.is_empty() && self.peek()? == CloseParentheses {
+                return Ok((variables, false));
+            }
+            // Names are optional in a prototype, and an array parameter is
+            // adjusted to a pointer
+            let location = self.location();
+            let mut variable = self.parse_declarator(size, qualifiers, false)?;
+            variable.size = variable.size.decay();
+            if variable.size == Size::Void {
+                return Err(ParseError::new_with_location(
+                    format!("parameter `{}` has void type", variable.name),
+                    location,
+                ));
+            }
+            if !variable.name.is_empty() {
+                self.declare(&variable.name, Name::Object(variable.size.clone(), false), location)?;
+            }
+            variables.push(variable);
+
+            if self.peek()? != Comma {
+                return Ok((variables, false));
+            }
+            self.next_token()?;
+        }
+    }
+
     fn parse_compound(&mut self) -> Result<Compound, ParseError> {
         //                println!("parse_compound {:?}", self.peek()?);
         match self.peek()? {
-            TokenType::Keyword(size @ Keyword::Int) => {
-                self.next_token()?;
-                let name = self.match_identifier()?;
-                match self.peek()? {
-                    Assignement => {
-                        //println!("Assignement");
+            TokenType::Keyword(Keyword::Typedef) => {
+                let location = self.location();
+                self.parse_typedef()?;
+                Ok(Compound::Statement(Statement::Expression(None, location)))
+            }
+            token
+                if self.is_type(&token)
+                    || matches!(token, TokenType::Keyword(Keyword::Static | Keyword::Extern)) =>
+            {
+                let storage = self.parse_storage()?;
+                let (size, qualifiers) = self.parse_type()?;
+                if self.peek()? == Semicolon {
+                    let location = self.location();
+                    self.next_token()?;
+                    return Ok(Compound::Statement(Statement::Expression(None, location)));
+                }
+                let mut declarators = vec![];
+                loop {
+                    let location = self.location();
+                    let mut variable = self.parse_object_declarator(size.clone(), qualifiers)?;
+                    let declared = Name::Object(variable.size.clone(), storage.is_some());
+                    self.declare(&variable.name, declared, location)?;
+                    let mut initializer = None;
+                    if self.peek()? == Assignement {
                         self.next_token()?;
-                        let expr = self.parse_expression()?;
-                        self.match_token(TokenType::Semicolon)?;
-                        Ok(Compound::Declare(Declare(
-                            Variable::new(name, size.into()),
-                            Some(expr),
-                        )))
+                        let location = self.location();
+                        if storage == Some(Storage::Extern) {
+                            return Err(ParseError::new_with_location(
+                                format!("`{}` has both `extern` and initializer", variable.name),
+                                location,
+                            ));
+                        }
+                        let parsed = self.parse_initializer(&mut variable)?;
+                        // The initializer may have completed the type
+                        let declared = Name::Object(variable.size.clone(), storage.is_some());
+                        self.declare(&variable.name, declared, location)?;
+                        initializer = Some(match storage {
+                            Some(Storage::Static) => {
+                                self.fold_constants(&variable, parsed, location)?
+                            }
+                            _ => parsed,
+                        });
                     }
-                    Semicolon => {
-                        //println!("Semicolon");
-                        self.match_token(TokenType::Semicolon)?;
-                        Ok(Compound::Declare(Declare(
-                            Variable::new(name, size.into()),
-                            None,
-                        )))
+                    declarators.push((variable, initializer, location));
+                    match self.peek()? {
+                        Comma => {
+                            self.next_token()?;
+                        }
+                        Semicolon => {
+                            self.next_token()?;
+                            return Ok(Compound::Declare(Declare(storage, declarators)));
+                        }
+                        t => {
+                            return Err(ParseError::new_with_token(
+                                format!("Error assigment in statement `{:?}`", t),
+                                &self.next_token()?,
+                            ))
+                        }
                     }
-                    t => Err(ParseError::new_with_token(
-                        format!("Error assigment in statement `{:?}`", t),
-                        &self.next_token()?,
-                    )),
                 }
             }
             _ => Ok(Compound::Statement(self.parse_statement()?)),
@@ -224,10 +1243,12 @@ impl Parse {
     parse_brace(&mut self) -> Result<Statement, ParseError> {
         if self.peek()? == OpenBrace {
             self.next_token()?;
+            self.enter_scope();
             let mut compounds = vec![];
             while self.next_is(TokenType::CloseBrace).is_err() {
                 compounds.push(self.parse_compound()?);
             }
+            self.leave_scope();
 
             self.next_token()?;
             Ok(Statement::Compound(compounds))
@@ -238,30 +1259,43 @@ impl Parse {
 
     fn parse_statement(&mut self) -> Result<Statement, ParseError> {
         println!("{:?}", self.peek());
+        let location = self.location();
         match self.peek()? {
             TokenType::Keyword(Keyword::Return) => {
                 self.next_token()?;
-                let statement = Ok(Statement::Return(self.parse_expression()?));
+                let expression = match self.peek()? {
+                    Semicolon => None,
+                    _ => Some(self.parse_expression()?),
+                };
                 self.match_token(TokenType::Semicolon)?;
-                statement
+                Ok(Statement::Return(expression, location))
             }
 
             TokenType::Identifier(_) => {
-                let statement = Ok(Statement::Expression(Some(self.parse_expression()?)));
+                let token = self.next_token()?;
+                if let (Identifier(name), Colon) = (&token.token, self.peek()?) {
+                    self.next_token()?;
+                    let statement = Box::new(self.parse_statement()?);
+                    return Ok(Statement::Label(name.clone(), statement, location));
+                }
+                self.push(token);
+                let statement = Ok(Statement::Expression(Some(self.parse_expression()?), location));
                 self.match_token(Semicolon)?;
                 statement
             }
             TokenType::Literal(_) => {
-                let statement = Ok(Statement::Expression(Some(self.parse_expression()?)));
+                let statement = Ok(Statement::Expression(Some(self.parse_expression()?), location));
                 self.match_token(Semicolon)?;
                 statement
             }
             TokenType::OpenBrace => {
                 self.next_token()?;
+                self.enter_scope();
                 let mut compounds = vec![];
                 while self.next_is(TokenType::CloseBrace).is_err() {
                     compounds.push(self.parse_compound()?);
                 }
+                self.leave_scope();
 
                 let statement = Statement::Compound(compounds);
                 self.next_token()?;
@@ -291,20 +1325,64 @@ impl Parse {
                     condition,
                     Box::new(statement),
                     else_statement,
+                    location,
                 ))
             }
-            TokenType::Keyword(Keyword::Break) => Ok(Statement::Break),
-            TokenType::Keyword(Keyword::Continue) => Ok(Statement::Continue),
+            TokenType::Keyword(Keyword::Switch) => {
+                self.next_token()?;
+                self.match_token(OpenParentheses)?;
+                let expression = self.parse_expression()?;
+                self.match_token(CloseParentheses)?;
+
+                let statement = Box::new(self.parse_brace()?);
+
+                Ok(Statement::Switch(expression, statement, location))
+            }
+            TokenType::Keyword(Keyword::Case) => {
+                self.next_token()?;
+                // The value is converted to the type of the switch later on
+                let value = self.parse_ternary_condition()?.evaluate().ok_or_else(|| {
+                    ParseError::new_with_location(
+                        "case label does not reduce to an integer constant".to_string(),
+                        location,
+                    )
+                })?;
+                self.match_token(Colon)?;
+                Ok(Statement::Case(value, Box::new(self.parse_statement()?), location))
+            }
+            TokenType::Keyword(Keyword::Default) => {
+                self.next_token()?;
+                self.match_token(Colon)?;
+                Ok(Statement::Default(Box::new(self.parse_statement()?), location))
+            }
+            TokenType::Keyword(Keyword::Goto) => {
+                self.next_token()?;
+                let label = self.match_identifier()?;
+                self.match_token(Semicolon)?;
+                Ok(Statement::Goto(label, location))
+            }
+            TokenType::Keyword(Keyword::Break) => {
+                self.next_token()?;
+                self.match_token(Semicolon)?;
+                Ok(Statement::Break(location))
+            }
+            TokenType::Keyword(Keyword::Continue) => {
+                self.next_token()?;
+                self.match_token(Semicolon)?;
+                Ok(Statement::Continue(location))
+            }
             TokenType::Keyword(Keyword::Do) => {
                 self.next_token()?;
 
                 self.match_token(OpenBrace)?;
 
+                self.enter_scope();
                 let mut compounds = vec![];
 
                 while self.peek()? != CloseBrace {
                     compounds.push(self.parse_compound()?);
                 }
+                self.leave_scope();
 
                 self.match_token(CloseBrace)?;
 
@@ -313,7 +1391,7 @@ impl Parse {
                 let expression = self.parse_expression()?;
                 self.match_token(CloseParentheses)?;
                 self.match_token(Semicolon)?;
-                Ok(Statement::Do(compounds, expression))
+                Ok(Statement::Do(compounds, expression, location))
             }
             TokenType::Keyword(Keyword::While) => {
                 self.next_token()?;
@@ -324,20 +1402,26 @@ impl Parse {
 
                 let statement = Box::new(self.parse_brace()?);
 
-                Ok(Statement::While(expression, statement))
+                Ok(Statement::While(expression, statement, location))
             }
             TokenType::Keyword(Keyword::For) => {
                 self.next_token()?;
                 self.match_token(OpenParentheses)?;
 
-                // Looking for declaration or assignation
+                // Looking for declaration or assignation, a declaration being
+                // in the scope of the loop
                 let initial;
+                self.enter_scope();
 
-                if self.peek()? == TokenType::Keyword(Keyword::Int) {
+                let token = self.peek()?;
+                if self.is_type(&token) {
                     if let Compound::Declare(declare) = self.parse_compound()? {
                         initial = First(declare);
                     } else {
-                        panic!("Declare")
+                        return Err(ParseError::new_with_location(
+                            "declaration in `for` loop declares no variable".to_string(),
+                            location,
+                        ));
                     }
                 } else if self.peek()? == Semicolon {
                     initial = Second(None);
@@ -362,6 +1446,7 @@ impl Parse {
                 self.match_token(CloseParentheses)?;
 
                 let statements = Box::new(self.parse_brace()?);
+                self.leave_scope();
 
                 match initial {
                     First(initial) => Ok(Statement::ForDecl(
@@ -369,22 +1454,26 @@ impl Parse {
                         condition,
                         post_expression,
                         statements,
+                        location,
                     )),
                     Second(initial) => Ok(Statement::For(
                         initial,
                         condition,
                         post_expression,
                         statements,
+                        location,
                     )),
                 }
             }
-            TokenType::Semicolon => Ok(Statement::Expression(Some(self.parse_expression()?))),
+            TokenType::Semicolon => {
+                Ok(Statement::Expression(Some(self.parse_expression()?), location))
+            }
             _ => {
                 let expression = self.parse_expression()?;
 
                 self.match_token(Semicolon)?;
 
-                Ok(Statement::Expression(Some(expression)))
+                Ok(Statement::Expression(Some(expression), location))
             }
         }
     }
@@ -401,45 +1490,65 @@ impl Parse {
             self.next_token()?;
             Ok(Expression::Int(1))
         } else {
-            self.parse_assignement()
+            self.parse_comma()
         }
     }
 
+    /// `a, b` evaluates `a` then `b`, and yields `b`.
+    fn parse_comma(&mut self) -> Result<Expression, ParseError> {
+        let mut expression = self.parse_assignement()?;
+        while self.peek()? == Comma {
+            self.next_token()?;
+            let right = self.parse_assignement()?;
+            expression = Expression::Comma(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    /// `a op= b` is lowered to `a = a op b`.
     fn generate_assignement(
         &mut self,
-        name: String,
-        token: BiOp,
+        lvalue: Expression,
+        token: TokenType,
+        location: Location,
     ) -> Result<Expression, ParseError> {
-        let bin_operator = BinaryOperator(
-            Box::new(Expression::Variable(name.clone())),
-            token,
-            Box::new(self.parse_expression()?),
-        );
-        Ok(Expression::Assign(name, Box::new(bin_operator)))
+        let value = self.parse_assignement()?;
+        match token {
+            Assignement => Ok(Expression::Assign(Box::new(lvalue), Box::new(value), location)),
+            token => Ok(Expression::CompoundAssign(
+                Box::new(lvalue),
+                token.into(),
+                Box::new(value),
+                location,
+            )),
+        }
     }
 
     fn parse_assignement(&mut self) -> Result<Expression, ParseError> {
-        let (a, b) = (self.next_token()?, self.next_token()?);
-        let (a_c, b_c) = (a.clone(), b.clone());
-        match (a.token, b.token) {
-            (Identifier(name), token @ Assignement) => {
-                self.generate_assignement(name, token.into())
-            }
-            (Identifier(name), token @ AssignPlus) => self.generate_assignement(name, token.into()),
-            (Identifier(name), token @ AssignMultiply) => {
-                self.generate_assignement(name, token.into())
-            }
-            (Identifier(name), token @ AssignMinus) => {
-                self.generate_assignement(name, token.into())
-            }
-            (Identifier(name), token @ AssignDivide) => {
-                self.generate_assignement(name, token.into())
-            }
-            (_, _) => {
-                self.push(b_c);
-                self.push(a_c);
-                self.parse_ternary_condition()
+        let location = self.location();
+        let expression = self.parse_ternary_condition()?;
+        match self.peek() {
+            Ok(token @ Assignement)
+            | Ok(token @ AssignPlus)
+            | Ok(token @ AssignMultiply)
+            | Ok(token @ AssignMinus)
+            | Ok(token @ AssignDivide)
+            | Ok(token @ AssignModulus)
+            | Ok(token @ AssignBitwiseLeft)
+            | Ok(token @ AssignBitwiseRight)
+            | Ok(token @ AssignAND)
+            | Ok(token @ AssignOR)
+            | Ok(token @ AssignXOR) => {
+                if !expression.is_lvalue() {
+                    return Err(ParseError::new_with_location(
+                        "lvalue required as left operand of assignment".to_string(),
+                        location,
+                    ));
+                }
+                self.next_token()?;
+                self.generate_assignement(expression, token, location)
             }
+            _ => Ok(expression),
         }
     }
 
@@ -456,96 +1565,241 @@ impl Parse {
     }
 
     fn parse_logical_or_operator(&mut self) -> Result<Expression, ParseError> {
-        self.parse_generate_expression(&[LogicalOr], &Parse::parse_logical_and_operator)
+        self.parse_generate_expression(&[LogicalOr], Parse::parse_logical_and_operator)
     }
 
     fn parse_logical_and_operator(&mut self) -> Result<Expression, ParseError> {
-        self.parse_generate_expression(&[LogicalAnd], &Parse::parse_bitwise_operator)
+        self.parse_generate_expression(&[LogicalAnd], Parse::parse_bitwise_operator)
     }
 
     fn parse_bitwise_operator(&mut self) -> Result<Expression, ParseError> {
         self.parse_generate_expression(
             &[BitwiseXOR, BitwiseAND, BitwiseOR],
-            &Parse::parse_equality_operator,
+            Parse::parse_equality_operator,
         )
     }
 
     fn parse_equality_operator(&mut self) -> Result<Expression, ParseError> {
-        self.parse_generate_expression(&[Equal, NotEqual], &Parse::parse_relational_operator)
+        self.parse_generate_expression(&[Equal, NotEqual], Parse::parse_relational_operator)
     }
 
     fn parse_relational_operator(&mut self) -> Result<Expression, ParseError> {
         self.parse_generate_expression(
             &[LessThan, LessOrEqual, GreaterThan, GreaterOrEqual],
-            &Parse::parse_bitwise_shift,
+            Parse::parse_bitwise_shift,
         )
     }
 
     fn parse_bitwise_shift(&mut self) -> Result<Expression, ParseError> {
         self.parse_generate_expression(
             &[BitwiseShiftRight, BitwiseShiftLeft],
-            &Parse::parse_lower_operator,
+            Parse::parse_lower_operator,
         )
     }
 
     fn parse_lower_operator(&mut self) -> Result<Expression, ParseError> {
-        self.parse_generate_expression(&[Addition, Minus], &Parse::parse_higher_operator)
+        self.parse_generate_expression(&[Addition, Minus], Parse::parse_higher_operator)
     }
 
     fn parse_higher_operator(&mut self) -> Result<Expression, ParseError> {
-        self.parse_generate_expression(&[Multiplication, Division, Modulus], &Parse::parse_factor)
+        self.parse_generate_expression(&[Multiplication, Division, Modulus], Parse::parse_factor)
     }
 
+    /// Unary operators, `&` and `*` being the address-of and dereference ones.
     fn parse_factor(&mut self) -> Result<Expression, ParseError> {
-        let token = self.next_token()?;
-        //println!("parse_factor {:?} {:?}", token.token, self.peek()?);
-        match (token.token, self.peek()?) {
-            (Identifier(name), token @ Increment) => {
-                self.generate_prefix_operator(token, name, true)
+        let location = self.location();
+        match self.peek()? {
+            token @ Increment | token @ Decrement => {
+                self.next_token()?;
+                let factor = self.parse_factor()?;
+                self.generate_prefix_operator(token, factor, true, location)
             }
-            (Identifier(name), token @ Decrement) => {
-                self.generate_prefix_operator(token, name, true)
+            tokentype @ TokenType::Bitwise
+            | tokentype @ TokenType::LogicalNegation
+            | tokentype @ TokenType::Minus
+            | tokentype @ TokenType::BitwiseAND
+            | tokentype @ TokenType::Multiplication => {
+                self.next_token()?;
+                let factor = self.parse_factor()?;
+                if tokentype == BitwiseAND && !factor.is_lvalue() {
+                    return Err(ParseError::new_with_location(
+                        "lvalue required as unary `&` operand".to_string(),
+                        location,
+                    ));
+                }
+                Ok(UnaryOperator(tokentype.into(), Box::new(factor)))
             }
-            (token @ Decrement, Identifier(name)) => {
-                self.generate_prefix_operator(token, name, false)
+            // The operand of `sizeof` is not evaluated, only its type matters
+            TokenType::Keyword(Keyword::Sizeof) => {
+                self.next_token()?;
+                let size = match self.parse_parenthesized_type()? {
+                    Some(size) => size,
+                    None => self
+                        .parse_factor()?
+                        .type_of(self)
+                        .map_err(|error| ParseError::new_with_location(error, location))?,
+                };
+                if !size.is_complete() || matches!(size, Size::Array(_, 0)) {
+                    return Err(ParseError::new_with_location(
+                        format!(
+                            "invalid application of `sizeof` to incomplete type `{:?}`",
+                            size
+                        ),
+                        location,
+                    ));
+                }
+                // `size_t`
+                Ok(Expression::ULong(i32::from(&size) as u64))
             }
-            (token @ Increment, Identifier(name)) => {
-                self.generate_prefix_operator(token, name, false)
+            OpenParentheses => match self.parse_parenthesized_type()? {
+                Some(size) if size.is_aggregate() => Err(ParseError::new_with_location(
+                    format!("conversion to non-scalar type `{:?}` requested", size),
+                    location,
+                )),
+                Some(size) => {
+                    let factor = self.parse_factor()?;
+                    Ok(Expression::Cast(size, Box::new(factor)))
+                }
+                None => self.parse_postfix(),
+            },
+            _ => self.parse_postfix(),
+        }
+    }
+
+    /// `( type-name )` of a cast or a `sizeof`, or `None` if the parenthesis
+    /// opens an expression, which is left to be parsed.
+    fn parse_parenthesized_type(&mut self) -> Result<Option<Size>, ParseError> {
+        if self.peek()? != OpenParentheses {
+            return Ok(None);
+        }
+        let parenthesis = self.next_token()?;
+        let next = self.peek()?;
+        if !self.is_type(&next) {
+            self.push(parenthesis);
+            return Ok(None);
+        }
+        let location = self.location();
+        let (size, qualifiers) = self.parse_type()?;
+        let variable = self.parse_declarator(size, qualifiers, false)?;
+        if !variable.name.is_empty() {
+            return Err(ParseError::new_with_location(
+                format!("unexpected name `{}` in type name", variable.name),
+                location,
+            ));
+        }
+        self.match_token(CloseParentheses)?;
+        Ok(Some(variable.size))
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expression, ParseError> {
+        let mut expression = self.parse_primary()?;
+        loop {
+            let location = self.location();
+            match self.peek() {
+                Ok(token @ Increment) | Ok(token @ Decrement) => {
+                    self.next_token()?;
+                    expression =
+                        self.generate_prefix_operator(token, expression, false, location)?;
+                }
+                Ok(Dot) => {
+                    self.next_token()?;
+                    let member = self.match_identifier()?;
+                    expression = Expression::Member(Box::new(expression), member);
+                }
+                Ok(Arrow) => {
+                    self.next_token()?;
+                    let member = self.match_identifier()?;
+                    let structure = UnaryOperator(UnOp::Dereference, Box::new(expression));
+                    expression = Expression::Member(Box::new(structure), member);
+                }
+                // `a[i]` is `*(a + i)`
+                Ok(OpenBracket) => {
+                    self.next_token()?;
+                    let index = self.parse_expression()?;
+                    self.match_token(CloseBracket)?;
+                    expression = UnaryOperator(
+                        UnOp::Dereference,
+                        Box::new(BinaryOperator(
+                            Box::new(expression),
+                            BiOp::Addition,
+                            Box::new(index),
+                        )),
+                    );
+                }
+                _ => return Ok(expression),
             }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, ParseError> {
+        let token = self.next_token()?;
+        let location = Location::from(&token);
+        //println!("parse_factor {:?} {:?}", token.token, self.peek()?);
+        match (token.token, self.peek()?) {
+            (Identifier(name), OpenParentheses) => self.parse_call(name, location),
             (Literal(Value::Int(nu)), _) => Ok(Expression::Int(nu)),
+            (Literal(Value::UInt(value)), _) => Ok(Expression::UInt(value)),
+            (Literal(Value::Long(value)), _) => Ok(Expression::Long(value)),
+            (Literal(Value::ULong(value)), _) => Ok(Expression::ULong(value)),
+            (Literal(Value::Float(value)), _) => Ok(Expression::Float(value)),
+            (Literal(Value::Double(value)), _) => Ok(Expression::Double(value)),
+            // A character constant has type int, with the value of a signed char
+            (Literal(Value::Char(c)), _) => Ok(Expression::Int(c as i8 as i32)),
+            (Literal(Value::String(s)), _) => Ok(Expression::String(s)),
             (OpenParentheses, _) => {
                 let expression = self.parse_expression();
                 self.match_token(CloseParentheses)?;
                 expression
             }
-            (tokentype @ TokenType::Bitwise, _)
-            | (tokentype @ TokenType::LogicalNegation, _)
-            | (tokentype @ TokenType::Minus, _) => {
-                let factor = self.parse_factor()?;
-                Ok(UnaryOperator(tokentype.into(), Box::new(factor)))
+            (Identifier(name), _) => match self.enumerators.get(&name) {
+                Some(value) => Ok(Expression::Enumerator(name, *value)),
+                None => Ok(Expression::Variable(name)),
+            },
+            (token, _) => Err(ParseError::new_with_location(
+                format!("error factor {:?} ", token),
+                location,
+            )),
+        }
+    }
+
+    fn parse_call(&mut self, name: String, location: Location) -> Result<Expression, ParseError> {
+        self.match_token(OpenParentheses)?;
+
+        let mut arguments = vec![];
+        if self.peek()? != CloseParentheses {
+            loop {
+                arguments.push(self.parse_assignement()?);
+                if self.peek()? != Comma {
+                    break;
+                }
+                self.next_token()?;
             }
-            (Identifier(name), _) => Ok(Expression::Variable(name)),
-            (token, _) => Err(ParseError::new(format!("error factor {:?} ", token), 3, 4)),
         }
+        self.match_token(CloseParentheses)?;
+
+        Ok(Expression::Call(name, arguments, location))
     }
 
+    /// `++a` is lowered to `a = a + 1`, `a++` to the same assignment
+    /// evaluating to the previous value.
     fn generate_prefix_operator(
         &mut self,
         token: TokenType,
-        name: String,
+        lvalue: Expression,
         prefix: bool,
+        location: Location,
     ) -> Result<Expression, ParseError> {
-        self.next_token()?;
-
-        let expression = Box::new(Expression::BinaryOperator(
-            Box::new(Expression::Variable(name.clone())),
-            token.into(),
-            Box::new(Expression::Int(1)),
-        ));
+        if !lvalue.is_lvalue() {
+            return Err(ParseError::new_with_location(
+                format!("lvalue required as {:?} operand", token),
+                location,
+            ));
+        }
 
+        let (lvalue, one) = (Box::new(lvalue), Box::new(Expression::Int(1)));
         match prefix {
-            true => Ok(Expression::Assign(name, expression)),
-            false => Ok(Expression::AssignPost(name, expression)),
+            true => Ok(Expression::CompoundAssign(lvalue, token.into(), one, location)),
+            false => Ok(Expression::AssignPost(lvalue, token.into(), one, location)),
         }
     }
 
@@ -0,0 +1,1081 @@
+use crate::parser::{Parse, ParseError};
+use crate::tokenizer::Tokenizer;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Deepest nesting of `#include`, so that a file including itself fails.
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Identifier,
+    /// Number, string or character literal, never expanded.
+    Literal,
+    Punctuator,
+    Space,
+}
+
+/// Preprocessing token, kept with its spelling.
+#[derive(Debug, Clone)]
+struct Token {
+    kind: Kind,
+    text: String,
+}
+
+impl Token {
+    fn new(kind: Kind, text: String) -> Token {
+        Token { kind, text }
+    }
+
+    fn space() -> Token {
+        Token::new(Kind::Space, " ".to_string())
+    }
+}
+
+/// Replacement list of a macro. A function-like macro has parameters, `...`
+/// being named `__VA_ARGS__`.
+#[derive(Debug, Clone)]
+struct Macro {
+    parameters: Option<Vec<String>>,
+    body: Vec<Token>,
+}
+
+/// State of an `#if` group.
+struct Conditional {
+    /// Whether the lines of the current branch are kept.
+    active: bool,
+    /// Whether a branch of the group was already kept, or the whole group is
+    /// skipped.
+    taken: bool,
+    else_seen: bool,
+}
+
+/// Expands the directives and the macros of a file into a single source. The
+/// output keeps the lines of the logical lines kept, and the original location
+/// of each of them is recorded to report errors.
+pub struct Preprocessor {
+    include_paths: Vec<PathBuf>,
+    macros: HashMap<String, Macro>,
+    /// Files containing `#pragma once` which were already included.
+    once: HashSet<PathBuf>,
+    /// Original file and line of every output line.
+    locations: Vec<(Rc<String>, usize)>,
+    /// File and line being preprocessed, which is where an error occurred.
+    file: Rc<String>,
+    line: usize,
+    depth: usize,
+}
+
+impl Preprocessor {
+    pub fn new(include_paths: Vec<PathBuf>) -> Preprocessor {
+        Preprocessor {
+            include_paths,
+            macros: HashMap::new(),
+            once: HashSet::new(),
+            locations: vec![],
+            file: Rc::new(String::new()),
+            line: 0,
+            depth: 0,
+        }
+    }
+
+    pub fn preprocess(&mut self, filename: &str) -> Result<String, ParseError> {
+        let mut output = String::new();
+        self.include(Path::new(filename), &mut output)?;
+        Ok(output)
+    }
+
+    /// Original file and line of a line of the output.
+    pub fn locate(&self, line: usize) -> (Rc<String>, usize) {
+        match self.locations.get(line).or_else(|| self.locations.last()) {
+            Some((file, line)) => (file.clone(), *line),
+            None => (self.file.clone(), line),
+        }
+    }
+
+    /// File in which preprocessing stopped, the one of an error.
+    pub fn file(&self) -> Rc<String> {
+        self.file.clone()
+    }
+
+    fn error(&self, error: String) -> ParseError {
+        ParseError::new(error, 0, self.line)
+    }
+
+    fn include(&mut self, path: &Path, output: &mut String) -> Result<(), ParseError> {
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if self.once.contains(&canonical) {
+            return Ok(());
+        }
+        if self.depth == MAX_INCLUDE_DEPTH {
+            return Err(self.error("#include nested too deeply".to_string()));
+        }
+        let source = fs::read_to_string(path)
+            .map_err(|e| self.error(format!("cannot read `{}`: {}", path.display(), e)))?;
+
+        let (file, line) = (self.file.clone(), self.line);
+        self.file = Rc::new(path.display().to_string());
+        self.depth += 1;
+
+        let lines = self.logical_lines(&source)?;
+        let mut conditionals: Vec<Conditional> = vec![];
+        let mut index = 0;
+        while index < lines.len() {
+            let (line, text) = &lines[index];
+            self.line = *line;
+            index += 1;
+
+            if let Some(directive) = text.trim_start().strip_prefix('#') {
+                let directive = directive.replace('\n', " ");
+                self.directive(&directive, &mut conditionals, path, &canonical, output)?;
+                continue;
+            }
+            if !conditionals.last().is_none_or(|c| c.active) {
+                continue;
+            }
+
+            // The arguments of a macro may span several lines
+            let mut text = text.clone();
+            let expanded = loop {
+                if let Some(expanded) = self.expand(&Self::lex(&text), &[])? {
+                    break expanded;
+                }
+                match lines.get(index) {
+                    Some((_, next)) if !next.trim_start().starts_with('#') => {
+                        text.push('\n');
+                        text.push_str(next);
+                        index += 1;
+                    }
+                    _ => {
+                        return Err(self.error("unterminated argument list of a macro".to_string()))
+                    }
+                }
+            };
+            // The newlines left in the line follow each other
+            let start = output.len();
+            for token in expanded {
+                output.push_str(&token.text);
+            }
+            output.push('\n');
+            for (offset, _) in output[start..].matches('\n').enumerate() {
+                self.locations.push((self.file.clone(), self.line + offset));
+            }
+        }
+        if !conditionals.is_empty() {
+            return Err(self.error("unterminated conditional directive".to_string()));
+        }
+
+        self.depth -= 1;
+        self.file = file;
+        self.line = line;
+        Ok(())
+    }
+
+    /// Splits a source in logical lines with the line they start on: a
+    /// backslash-newline joins two lines and a comment is replaced by a space.
+    /// The newlines joined are kept in the line at the first space following
+    /// them, so that its tokens are located on their own line.
+    fn logical_lines(&mut self, source: &str) -> Result<Vec<(usize, String)>, ParseError> {
+        let mut lines = vec![];
+        let mut current = String::new();
+        let mut start = 0;
+        let mut line = 0;
+        let mut chars = source.chars().peekable();
+        // Quote of the literal being read
+        let mut quote = None;
+        // Newlines joined in the middle of a token
+        let mut pending = 0;
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                    line += 1;
+                    if quote.is_none() && current.ends_with(char::is_whitespace) {
+                        current.push('\n');
+                    } else {
+                        pending += 1;
+                    }
+                }
+                '\n' => {
+                    line += 1;
+                    current.extend(std::iter::repeat_n('\n', pending));
+                    pending = 0;
+                    lines.push((start, std::mem::take(&mut current)));
+                    start = line;
+                    quote = None;
+                }
+                c if c.is_whitespace() && quote.is_none() && pending > 0 => {
+                    current.push(c);
+                    current.extend(std::iter::repeat_n('\n', pending));
+                    pending = 0;
+                }
+                '\\' if quote.is_some() => {
+                    current.push(c);
+                    if let Some(&next) = chars.peek() {
+                        if next != '\n' {
+                            current.push(next);
+                            chars.next();
+                        }
+                    }
+                }
+                '"' | '\'' if quote.is_none() => {
+                    quote = Some(c);
+                    current.push(c);
+                }
+                c if quote == Some(c) => {
+                    quote = None;
+                    current.push(c);
+                }
+                '/' if quote.is_none() && chars.peek() == Some(&'/') => {
+                    while chars.peek().is_some_and(|c| *c != '\n') {
+                        chars.next();
+                    }
+                }
+                '/' if quote.is_none() && chars.peek() == Some(&'*') => {
+                    chars.next();
+                    current.extend(std::iter::repeat_n('\n', pending));
+                    pending = 0;
+                    let comment = line;
+                    loop {
+                        match chars.next() {
+                            Some('*') if chars.peek() == Some(&'/') => {
+                                chars.next();
+                                break;
+                            }
+                            Some('\n') => {
+                                line += 1;
+                                current.push('\n');
+                            }
+                            Some(_) => {}
+                            None => {
+                                self.line = comment;
+                                return Err(self.error("unterminated comment".to_string()));
+                            }
+                        }
+                    }
+                    current.push(' ');
+                }
+                c => current.push(c),
+            }
+        }
+        current.extend(std::iter::repeat_n('\n', pending));
+        if !current.is_empty() {
+            lines.push((start, current));
+        }
+        Ok(lines)
+    }
+
+    fn lex(text: &str) -> Vec<Token> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut tokens = vec![];
+        let mut i = 0;
+        while i < chars.len() {
+            let start = i;
+            let c = chars[i];
+            i += 1;
+            let kind = if c.is_whitespace() {
+                while i < chars.len() && chars[i].is_whitespace() {
+                    i += 1;
+                }
+                Kind::Space
+            } else if c.is_alphabetic() || c == '_' {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Kind::Identifier
+            } else if c.is_ascii_digit()
+                || (c == '.' && chars.get(i).is_some_and(|c| c.is_ascii_digit()))
+            {
+                // Preprocessing number, exponents included
+                while i < chars.len() {
+                    match chars[i] {
+                        '+' | '-' if "eEpP".contains(chars[i - 1]) => i += 1,
+                        c if c.is_alphanumeric() || c == '_' || c == '.' => i += 1,
+                        _ => break,
+                    }
+                }
+                Kind::Literal
+            } else if c == '"' || c == '\'' {
+                while i < chars.len() && chars[i] != c {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                Kind::Literal
+            } else {
+                if c == '#' && chars.get(i) == Some(&'#') {
+                    i += 1;
+                } else if c == '.' && chars.get(i) == Some(&'.') && chars.get(i + 1) == Some(&'.') {
+                    i += 2;
+                }
+                Kind::Punctuator
+            };
+            tokens.push(Token::new(kind, chars[start..i].iter().collect()));
+        }
+        tokens
+    }
+
+    /// Index of the first token from `index` which is not a space.
+    fn skip_spaces(tokens: &[Token], mut index: usize) -> usize {
+        while index < tokens.len() && tokens[index].kind == Kind::Space {
+            index += 1;
+        }
+        index
+    }
+
+    fn directive(
+        &mut self,
+        directive: &str,
+        conditionals: &mut Vec<Conditional>,
+        path: &Path,
+        canonical: &Path,
+        output: &mut String,
+    ) -> Result<(), ParseError> {
+        let tokens = Self::lex(directive);
+        let start = Self::skip_spaces(&tokens, 0);
+        let name = match tokens.get(start) {
+            Some(token) if token.kind == Kind::Identifier => token.text.as_str(),
+            // Null directive
+            None => return Ok(()),
+            Some(token) => {
+                return Err(self.error(format!("invalid preprocessing directive `#{}`", token.text)))
+            }
+        };
+        let rest: String = tokens[start + 1..]
+            .iter()
+            .map(|t| t.text.as_str())
+            .collect();
+        let rest = rest.trim();
+        let active = conditionals.last().is_none_or(|c| c.active);
+
+        match name {
+            "if" | "ifdef" | "ifndef" => {
+                let active = active
+                    && match name {
+                        "if" => self.evaluate(rest)? != 0,
+                        "ifdef" => self.macros.contains_key(self.macro_name(rest)?),
+                        _ => !self.macros.contains_key(self.macro_name(rest)?),
+                    };
+                let taken = active || !conditionals.last().is_none_or(|c| c.active);
+                conditionals.push(Conditional {
+                    active,
+                    taken,
+                    else_seen: false,
+                });
+            }
+            "elif" => {
+                let taken = match conditionals.last() {
+                    Some(c) if c.else_seen => {
+                        return Err(self.error("#elif after #else".to_string()))
+                    }
+                    Some(c) => c.taken,
+                    None => return Err(self.error("#elif without #if".to_string())),
+                };
+                let active = !taken && self.evaluate(rest)? != 0;
+                if let Some(c) = conditionals.last_mut() {
+                    c.active = active;
+                    c.taken |= active;
+                }
+            }
+            "else" => match conditionals.last_mut() {
+                Some(c) if c.else_seen => return Err(self.error("#else after #else".to_string())),
+                Some(c) => {
+                    c.active = !c.taken;
+                    c.taken = true;
+                    c.else_seen = true;
+                }
+                None => return Err(self.error("#else without #if".to_string())),
+            },
+            "endif" => {
+                if conditionals.pop().is_none() {
+                    return Err(self.error("#endif without #if".to_string()));
+                }
+            }
+            // The other directives of a skipped group are ignored
+            _ if !active => {}
+            "define" => self.define(&tokens[start + 1..])?,
+            "undef" => {
+                let name = self.macro_name(rest)?.to_string();
+                self.macros.remove(&name);
+            }
+            "include" => {
+                let file = self.find_include(rest, path)?;
+                self.include(&file, output)?;
+            }
+            "error" => return Err(self.error(format!("#error {}", rest))),
+            "pragma" if rest == "once" => {
+                self.once.insert(canonical.to_path_buf());
+            }
+            // Unknown pragmas are ignored
+            "pragma" => {}
+            name => return Err(self.error(format!("invalid preprocessing directive `#{}`", name))),
+        }
+        Ok(())
+    }
+
+    fn macro_name<'b>(&self, text: &'b str) -> Result<&'b str, ParseError> {
+        match Self::lex(text).as_slice() {
+            [token] if token.kind == Kind::Identifier => Ok(text),
+            _ => Err(self.error(format!("macro names must be identifiers, not `{}`", text))),
+        }
+    }
+
+    /// `#define NAME body` or `#define NAME(parameters) body`, the parameters
+    /// having to follow the name without a space.
+    fn define(&mut self, tokens: &[Token]) -> Result<(), ParseError> {
+        let start = Self::skip_spaces(tokens, 0);
+        let name = match tokens.get(start) {
+            Some(token) if token.kind == Kind::Identifier => token.text.clone(),
+            _ => return Err(self.error("macro names must be identifiers".to_string())),
+        };
+        if name == "defined" {
+            return Err(self.error("`defined` cannot be used as a macro name".to_string()));
+        }
+
+        let mut index = start + 1;
+        let parameters = match tokens.get(index) {
+            Some(token) if token.text == "(" => {
+                let mut parameters = vec![];
+                index = Self::skip_spaces(tokens, index + 1);
+                if tokens.get(index).map(|t| t.text.as_str()) == Some(")") {
+                    index += 1;
+                } else {
+                    loop {
+                        match tokens.get(index) {
+                            Some(token) if token.kind == Kind::Identifier => {
+                                if parameters.contains(&token.text) {
+                                    return Err(self.error(format!(
+                                        "duplicate macro parameter `{}`",
+                                        token.text
+                                    )));
+                                }
+                                parameters.push(token.text.clone());
+                            }
+                            Some(token) if token.text == "..." => {
+                                parameters.push("__VA_ARGS__".to_string());
+                            }
+                            _ => {
+                                return Err(self
+                                    .error(format!("invalid parameter list of macro `{}`", name)))
+                            }
+                        }
+                        index = Self::skip_spaces(tokens, index + 1);
+                        match tokens.get(index).map(|t| t.text.as_str()) {
+                            Some(",") if parameters.last().unwrap() != "__VA_ARGS__" => {
+                                index = Self::skip_spaces(tokens, index + 1);
+                            }
+                            Some(")") => {
+                                index += 1;
+                                break;
+                            }
+                            _ => {
+                                return Err(self
+                                    .error(format!("invalid parameter list of macro `{}`", name)))
+                            }
+                        }
+                    }
+                }
+                Some(parameters)
+            }
+            _ => None,
+        };
+
+        let mut body = tokens[index..].to_vec();
+        while body.first().is_some_and(|t| t.kind == Kind::Space) {
+            body.remove(0);
+        }
+        while body.last().is_some_and(|t| t.kind == Kind::Space) {
+            body.pop();
+        }
+        if body.first().is_some_and(|t| t.text == "##")
+            || body.last().is_some_and(|t| t.text == "##")
+        {
+            return Err(
+                self.error("`##` cannot appear at either end of a macro expansion".to_string())
+            );
+        }
+        if let Some(parameters) = &parameters {
+            for (i, token) in body.iter().enumerate() {
+                let next = body.get(Self::skip_spaces(&body, i + 1));
+                if token.text == "#" && !next.is_some_and(|t| parameters.contains(&t.text)) {
+                    return Err(self.error("`#` is not followed by a macro parameter".to_string()));
+                }
+            }
+        }
+
+        self.macros.insert(name, Macro { parameters, body });
+        Ok(())
+    }
+
+    /// `"file"` is searched next to the including file first, `<file>` only
+    /// in the `-I` directories. Any other form is macro expanded first.
+    fn find_include(&self, text: &str, path: &Path) -> Result<PathBuf, ParseError> {
+        let text = if text.starts_with('"') || text.starts_with('<') {
+            text.to_string()
+        } else {
+            let tokens = self
+                .expand(&Self::lex(text), &[])?
+                .ok_or_else(|| self.error("unterminated argument list of a macro".to_string()))?;
+            tokens.iter().map(|t| t.text.as_str()).collect::<String>()
+        };
+        let text = text.trim();
+
+        let (name, mut directories) =
+            if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+                let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                (&text[1..text.len() - 1], vec![directory])
+            } else if text.len() >= 2 && text.starts_with('<') && text.ends_with('>') {
+                (&text[1..text.len() - 1], vec![])
+            } else {
+                return Err(self.error("#include expects \"FILENAME\" or <FILENAME>".to_string()));
+            };
+        directories.extend(self.include_paths.iter().cloned());
+        directories
+            .into_iter()
+            .map(|directory| directory.join(name))
+            .find(|file| file.is_file())
+            .ok_or_else(|| self.error(format!("`{}` file not found", name)))
+    }
+
+    /// Value of the expression of an `#if`, folded by the parser once
+    /// `defined` and the macros are replaced. The identifiers left are 0.
+    fn evaluate(&self, text: &str) -> Result<i64, ParseError> {
+        let tokens = Self::lex(text);
+        let mut replaced = vec![];
+        let mut i = 0;
+        while i < tokens.len() {
+            if tokens[i].text != "defined" {
+                replaced.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+            i = Self::skip_spaces(&tokens, i + 1);
+            let parenthesized = tokens.get(i).is_some_and(|t| t.text == "(");
+            if parenthesized {
+                i = Self::skip_spaces(&tokens, i + 1);
+            }
+            let name = match tokens.get(i) {
+                Some(token) if token.kind == Kind::Identifier => token.text.clone(),
+                _ => {
+                    return Err(self.error("operator `defined` requires an identifier".to_string()))
+                }
+            };
+            i += 1;
+            if parenthesized {
+                i = Self::skip_spaces(&tokens, i);
+                if tokens.get(i).is_none_or(|t| t.text != ")") {
+                    return Err(self.error("missing `)` after `defined`".to_string()));
+                }
+                i += 1;
+            }
+            let value = if self.macros.contains_key(&name) {
+                "1"
+            } else {
+                "0"
+            };
+            replaced.push(Token::new(Kind::Literal, value.to_string()));
+        }
+
+        let expanded = self
+            .expand(&replaced, &[])?
+            .ok_or_else(|| self.error("unterminated argument list of a macro".to_string()))?;
+        let text: String = expanded
+            .iter()
+            .map(|t| match t.kind {
+                Kind::Identifier => "0",
+                _ => t.text.as_str(),
+            })
+            .collect();
+        if text.trim().is_empty() {
+            return Err(self.error("#if with no expression".to_string()));
+        }
+
+        // The parser needs a token after the expression
+        let mut tokenizer = Tokenizer::new(format!("{};", text));
+        tokenizer
+            .tokenize()
+            .and_then(|_| Parse::new(tokenizer.tokens).parse_constant())
+            .map_err(|e| self.error(format!("invalid #if expression: {}", e.error)))
+    }
+
+    /// Replaces the macros of a list of tokens, `None` if the arguments of a
+    /// function-like macro go past its end. A macro is not replaced again in
+    /// its own expansion, where `disabled` holds it.
+    fn expand(
+        &self,
+        tokens: &[Token],
+        disabled: &[String],
+    ) -> Result<Option<Vec<Token>>, ParseError> {
+        let mut output = vec![];
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = &tokens[i];
+            i += 1;
+            if token.kind != Kind::Identifier || disabled.contains(&token.text) {
+                output.push(token.clone());
+                continue;
+            }
+            match token.text.as_str() {
+                "__FILE__" => {
+                    let file = self.file.replace('\\', "\\\\").replace('"', "\\\"");
+                    output.push(Token::new(Kind::Literal, format!("\"{}\"", file)));
+                    continue;
+                }
+                "__LINE__" => {
+                    output.push(Token::new(Kind::Literal, (self.line + 1).to_string()));
+                    continue;
+                }
+                _ => {}
+            }
+            let definition = match self.macros.get(&token.text) {
+                Some(definition) => definition,
+                None => {
+                    output.push(token.clone());
+                    continue;
+                }
+            };
+
+            // Newlines of a call are moved after its expansion
+            let mut newlines = String::new();
+            let replacement = match &definition.parameters {
+                None => Self::substitute(&definition.body, &[], &[], &[]),
+                Some(parameters) => {
+                    // The name of a function-like macro alone is not replaced
+                    let open = Self::skip_spaces(tokens, i);
+                    if tokens.get(open).is_none_or(|t| t.text != "(") {
+                        output.push(token.clone());
+                        continue;
+                    }
+                    let (arguments, next) = match Self::arguments(tokens, open + 1) {
+                        Some(arguments) => arguments,
+                        None => return Ok(None),
+                    };
+                    newlines = tokens[i..next]
+                        .iter()
+                        .flat_map(|t| t.text.chars().filter(|c| *c == '\n'))
+                        .collect();
+                    i = next;
+                    let arguments = self.match_arguments(&token.text, parameters, arguments)?;
+                    let mut expanded = vec![];
+                    for argument in &arguments {
+                        expanded.push(
+                            self.expand(argument, disabled)?
+                                .unwrap_or_else(|| argument.clone()),
+                        );
+                    }
+                    Self::substitute(&definition.body, parameters, &arguments, &expanded)
+                }
+            };
+
+            let mut disabled = disabled.to_vec();
+            disabled.push(token.text.clone());
+            let rescanned = self.expand(&replacement, &disabled)?;
+            // Spaces keep the expansion from pasting into its neighbours
+            output.push(Token::space());
+            output.extend(rescanned.unwrap_or(replacement));
+            output.push(Token::space());
+            if !newlines.is_empty() {
+                output.push(Token::new(Kind::Space, newlines));
+            }
+        }
+        Ok(Some(output))
+    }
+
+    /// Arguments of a macro call from the token after `(` to the matching
+    /// `)`, split on the commas outside of parentheses, with the index of the
+    /// token following the call.
+    fn arguments(tokens: &[Token], start: usize) -> Option<(Vec<Vec<Token>>, usize)> {
+        let mut arguments = vec![vec![]];
+        let mut depth = 0;
+        for (i, token) in tokens.iter().enumerate().skip(start) {
+            match token.text.as_str() {
+                ")" if depth == 0 => {
+                    let arguments = arguments.into_iter().map(Self::trim).collect();
+                    return Some((arguments, i + 1));
+                }
+                "," if depth == 0 => {
+                    arguments.push(vec![]);
+                    continue;
+                }
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                _ => {}
+            }
+            let token = if token.kind == Kind::Space {
+                Token::space()
+            } else {
+                token.clone()
+            };
+            arguments.last_mut().unwrap().push(token);
+        }
+        None
+    }
+
+    fn trim(mut tokens: Vec<Token>) -> Vec<Token> {
+        while tokens.last().is_some_and(|t| t.kind == Kind::Space) {
+            tokens.pop();
+        }
+        let start = Self::skip_spaces(&tokens, 0);
+        tokens.split_off(start)
+    }
+
+    /// Checks the number of arguments, the extra ones of a variadic macro
+    /// being gathered with their commas in `__VA_ARGS__`.
+    fn match_arguments(
+        &self,
+        name: &str,
+        parameters: &[String],
+        mut arguments: Vec<Vec<Token>>,
+    ) -> Result<Vec<Vec<Token>>, ParseError> {
+        // `f()` has one empty argument
+        if parameters.is_empty() && arguments.len() == 1 && arguments[0].is_empty() {
+            arguments.clear();
+        }
+        let variadic = parameters.last().is_some_and(|p| p == "__VA_ARGS__");
+        if variadic && arguments.len() + 1 == parameters.len() {
+            arguments.push(vec![]);
+        }
+        if variadic && arguments.len() > parameters.len() {
+            let extra = arguments.split_off(parameters.len());
+            let last = arguments.last_mut().unwrap();
+            for argument in extra {
+                last.push(Token::new(Kind::Punctuator, ",".to_string()));
+                last.extend(argument);
+            }
+        }
+        if arguments.len() != parameters.len() {
+            return Err(self.error(format!(
+                "macro `{}` requires {} argument(s), but {} given",
+                name,
+                parameters.len(),
+                arguments.len()
+            )));
+        }
+        Ok(arguments)
+    }
+
+    /// Replacement list of a macro with its parameters replaced: `#p` is the
+    /// spelling of the argument as a string literal, and the operands of `##`
+    /// are pasted without being expanded.
+    fn substitute(
+        body: &[Token],
+        parameters: &[String],
+        arguments: &[Vec<Token>],
+        expanded: &[Vec<Token>],
+    ) -> Vec<Token> {
+        let parameter = |token: &Token| {
+            if token.kind == Kind::Identifier {
+                parameters.iter().position(|p| p == &token.text)
+            } else {
+                None
+            }
+        };
+        let mut result: Vec<Token> = vec![];
+        let mut i = 0;
+        while i < body.len() {
+            let token = &body[i];
+            let next = Self::skip_spaces(body, i + 1);
+            if token.text == "#" && !parameters.is_empty() {
+                if let Some(p) = body.get(next).and_then(&parameter) {
+                    result.push(Self::stringify(&arguments[p]));
+                    i = next + 1;
+                    continue;
+                }
+            }
+            if token.text == "##" {
+                while result.last().is_some_and(|t| t.kind == Kind::Space) {
+                    result.pop();
+                }
+                let mut right = match body.get(next) {
+                    Some(t) => match parameter(t) {
+                        Some(p) => arguments[p].clone().into_iter(),
+                        None => vec![t.clone()].into_iter(),
+                    },
+                    None => vec![].into_iter(),
+                };
+                match (result.pop(), right.next()) {
+                    (Some(first), Some(second)) => {
+                        result.extend(Self::lex(&(first.text + &second.text)))
+                    }
+                    (first, second) => result.extend(first.into_iter().chain(second)),
+                }
+                result.extend(right);
+                i = next + 1;
+                continue;
+            }
+            match parameter(token) {
+                Some(p) if body.get(next).is_some_and(|t| t.text == "##") => {
+                    result.extend(arguments[p].iter().cloned())
+                }
+                Some(p) => result.extend(expanded[p].iter().cloned()),
+                None => result.push(token.clone()),
+            }
+            i += 1;
+        }
+        result
+    }
+
+    /// String literal spelling an argument, its spaces reduced to one.
+    fn stringify(argument: &[Token]) -> Token {
+        let mut text = String::from("\"");
+        for token in argument {
+            match token.kind {
+                Kind::Space => text.push(' '),
+                Kind::Literal => {
+                    text.push_str(&token.text.replace('\\', "\\\\").replace('"', "\\\""))
+                }
+                _ => text.push_str(&token.text),
+            }
+        }
+        text.push('"');
+        Token::new(Kind::Literal, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Kind, Preprocessor};
+    use crate::parser::ParseError;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Writes `source` as the file `name` of a temporary directory.
+    fn write(name: &str, source: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("preprocessor-tests-{}", std::process::id()));
+        fs::create_dir_all(&directory).expect("temporary directory");
+        let file = directory.join(name);
+        fs::write(&file, source).expect("source file");
+        file
+    }
+
+    fn preprocess(name: &str, source: &str) -> Result<String, ParseError> {
+        let file = write(name, source);
+        Preprocessor::new(vec![]).preprocess(file.to_str().expect("path"))
+    }
+
+    /// Tokens of a line, the spaces left out.
+    fn tokens(text: &str) -> Vec<String> {
+        Preprocessor::lex(text)
+            .into_iter()
+            .filter(|token| token.kind != Kind::Space)
+            .map(|token| token.text)
+            .collect()
+    }
+
+    /// Compares the tokens of the non empty lines of the output.
+    fn assert_output(name: &str, source: &str, expected: &[&str]) {
+        let output = preprocess(name, source).unwrap_or_else(|error| panic!("{}", error.error));
+        let lines: Vec<Vec<String>> = output
+            .lines()
+            .map(tokens)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let expected: Vec<Vec<String>> = expected.iter().map(|line| tokens(line)).collect();
+        assert_eq!(lines, expected, "{}", output);
+    }
+
+    fn error(name: &str, source: &str) -> ParseError {
+        match preprocess(name, source) {
+            Ok(output) => panic!("`{}` is accepted: {}", name, output),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn object_and_function_like_macros() {
+        let source = r#"
+#define N 10
+#define SQUARE(x) ((x) * (x))
+#define MAX(a, b) ((a) > (b) ? (a) : (b))
+int a = N;
+int b = SQUARE(N + 1);
+int c = MAX(SQUARE(2),
+            3);
+int SQUARE;
+#undef N
+int N;
+"#;
+        let expected = [
+            "int a = 10;",
+            "int b = ((10 + 1) * (10 + 1));",
+            "int c = ((((2) * (2))) > (3) ? (((2) * (2))) : (3))",
+            ";",
+            "int SQUARE;",
+            "int N;",
+        ];
+        assert_output("macros.c", source, &expected);
+    }
+
+    #[test]
+    fn stringizing_pasting_and_variadic_macros() {
+        let source = r#"
+#define STR(x) #x
+#define CAT(a, b) a ## b
+#define CALL(f, ...) f(__VA_ARGS__)
+#define foo foo + 1
+#define ping pong
+#define pong ping
+char *s = STR(a  +  "b\n");
+int CAT(var, 1) = CAT(1, 2);
+CALL(g, 1, (2, 3));
+CALL(h);
+foo;
+ping;
+int line = __LINE__;
+"#;
+        let expected = [
+            r#"char *s = "a + \"b\\n\"";"#,
+            "int var1 = 12;",
+            "g(1, (2, 3));",
+            "h();",
+            "foo + 1;",
+            "ping;",
+            "int line = 14;",
+        ];
+        assert_output("operators.c", source, &expected);
+
+        let message = error("arguments.c", "#define F(a, b) a\nF(1);\n").error;
+        assert!(message.contains("`F`"), "{}", message);
+    }
+
+    #[test]
+    fn conditional_directives() {
+        let source = r#"
+#define A 2
+#if A == 2 && defined(A)
+one
+#elif 1
+not one
+#else
+not one either
+#endif
+#if !defined B
+two
+#elif 1
+not two
+#endif
+#if 0
+#error skipped
+#elif A > 1
+three
+#endif
+#ifdef UNDEFINED
+not four
+#else
+four
+#endif
+#if UNDEFINED
+not five
+#elif (0u - 1) > 0 && -1 < 0
+five
+#endif
+#ifndef A
+#if 1
+nested
+#endif
+#endif
+"#;
+        let expected = ["one", "two", "three", "four", "five"];
+        assert_output("conditionals.c", source, &expected);
+
+        let cases = [
+            ("#if 1\n#else\n#elif 1\n#endif\n", "#elif after #else"),
+            ("#else\n", "#else without #if"),
+            ("#if 1\n", "unterminated conditional directive"),
+            ("#if\n#endif\n", "#if with no expression"),
+            ("#if defined(\n#endif\n", "operator `defined` requires an identifier"),
+            ("#define X 1\n#if X\n#error stop here\n#endif\n", "#error stop here"),
+        ];
+        for (index, (source, message)) in cases.iter().enumerate() {
+            let error = error(&format!("conditional{}.c", index), source);
+            assert!(error.error.contains(message), "{}: {}", source, error.error);
+        }
+    }
+
+    #[test]
+    fn output_lines_are_located_in_their_file() {
+        let header = write("located.h", "#pragma once\nint h1;\n\nint h2;\n");
+        let source = format!(
+            concat!(
+                "#include \"{}\"\nint a;\n#include \"located.h\"\n",
+                "#define F(x) x\nint b = F(\n1);\nint \\\nc;\n"
+            ),
+            header.display()
+        );
+        let file = write("located.c", &source);
+        let mut preprocessor = Preprocessor::new(vec![]);
+        let output = preprocessor
+            .preprocess(file.to_str().expect("path"))
+            .expect("output");
+        let located: Vec<(String, usize, String)> = output
+            .lines()
+            .enumerate()
+            .map(|(index, line)| {
+                let (file, line_number) = preprocessor.locate(index);
+                let name = file.rsplit('/').next().unwrap_or_default().to_string();
+                (name, line_number, tokens(line).concat())
+            })
+            .collect();
+        let expected = [
+            ("located.h", 1, "inth1;"),
+            ("located.h", 2, ""),
+            ("located.h", 3, "inth2;"),
+            ("located.c", 1, "inta;"),
+            ("located.c", 4, "intb=1"),
+            ("located.c", 5, ";"),
+            ("located.c", 6, "int"),
+            ("located.c", 7, "c;"),
+        ];
+        let expected: Vec<(String, usize, String)> = expected
+            .iter()
+            .map(|(file, line, text)| (file.to_string(), *line, text.to_string()))
+            .collect();
+        assert_eq!(located, expected);
+        // Lines past the output are those of the end of the source
+        assert_eq!(preprocessor.locate(100).1, 7);
+    }
+
+    #[test]
+    fn joined_lines_keep_the_lines_of_their_tokens() {
+        let source = concat!(
+            "int a; /* one\ntwo */ int b;\n",
+            "#define F(x, y) x + y\n",
+            "int c = F(1,\n2); int d;\n",
+            "int e = 1 + \\\n2; char *s = \"x\\\ny\"; int f;\n",
+            "#define G /* one\ntwo */ 3\n",
+            "int g = G;\n",
+        );
+        let file = write("joined.c", source);
+        let mut preprocessor = Preprocessor::new(vec![]);
+        let output = preprocessor
+            .preprocess(file.to_str().expect("path"))
+            .expect("output");
+        let located: Vec<(usize, String)> = output
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (preprocessor.locate(index).1, tokens(line).concat()))
+            .filter(|(_, text)| !text.is_empty())
+            .collect();
+        let expected = [
+            (0, "inta;"),
+            (1, "intb;"),
+            (3, "intc=1+2"),
+            (4, ";intd;"),
+            (5, "inte=1+"),
+            (6, "2;char*s=\"xy\";"),
+            (7, "intf;"),
+            (10, "intg=3;"),
+        ];
+        let expected: Vec<(usize, String)> = expected
+            .iter()
+            .map(|(line, text)| (*line, text.to_string()))
+            .collect();
+        assert_eq!(located, expected, "{}", output);
+    }
+}
@@ -1,10 +1,8 @@
+use crate::parser::ParseError;
 use std::fmt::{Error, Formatter};
-use std::fs::File;
-use std::io::Read;
-use std::rc::Rc;
 
 #[allow(dead_code)]
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq)]
 pub struct Token {
     pub line: usize,
     pub position: usize,
@@ -28,14 +26,20 @@ impl Token {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Int(i32),
+    UInt(u32),
+    Long(i64),
+    ULong(u64),
+    Float(f32),
+    Double(f64),
     Char(u8),
+    String(Vec<u8>),
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     Keyword(Keyword),
     Identifier(String),
@@ -44,7 +48,13 @@ pub enum TokenType {
     CloseParentheses,   // )
     OpenBrace,          // {
     CloseBrace,         // }
+    OpenBracket,        // [
+    CloseBracket,       // ]
     Semicolon,          // ;
+    Comma,              // ,
+    Dot,                // .
+    Ellipsis,           // ...
+    Arrow,              // ->
     Whitespace,         // ' '
     Minus,              // -
     Bitwise,            // ~
@@ -75,8 +85,8 @@ pub enum TokenType {
     BitwiseXOR,         // ^
     BitwiseAND,         // &
     BitwiseOR,          // |
-    BitwiseShiftLeft,   // >>
-    BitwiseShiftRight,  // <<
+    BitwiseShiftLeft,   // <<
+    BitwiseShiftRight,  // >>
     Colon,              // :
     QuestionMark,       // ?
     Increment,          // ++
@@ -87,6 +97,27 @@ pub enum TokenType {
 pub enum Keyword {
     Return,
     Int,
+    Char,
+    Float,
+    Double,
+    Void,
+    Short,
+    Long,
+    Signed,
+    Unsigned,
+    Struct,
+    Union,
+    Enum,
+    Typedef,
+    Static,
+    Extern,
+    Const,
+    Volatile,
+    Switch,
+    Case,
+    Default,
+    Goto,
+    Sizeof,
     If,
     Else,
     Continue,
@@ -109,33 +140,23 @@ pub enum CharacterType {
 #[derive(Debug)]
 pub struct Tokenizer {
     ptr: Vec<char>,
-    pub file: String,
     position: usize,
     line: usize,
     pub tokens: Vec<Token>,
 }
 
 impl Tokenizer {
-    pub fn new(filename: Rc<String>) -> Self {
-        let file = Self::read_file(filename);
+    /// Tokenizer of a preprocessed source.
+    pub fn new(file: String) -> Self {
         //        println!("{}", file);
         Tokenizer {
             ptr: file.chars().collect(),
-            file,
             position: 0,
             line: 0,
             tokens: vec![],
         }
     }
 
-    fn read_file(filename: Rc<String>) -> String {
-        let mut s = String::new();
-        let mut file = File::open(filename.as_str()).expect("File not found");
-        file.read_to_string(&mut s).expect("Error reading file");
-
-        s
-    }
-
     fn add_token(&mut self, token: TokenType) {
         self.tokens
             .push(Token::new(token, self.position, self.line));
@@ -159,12 +180,12 @@ impl Tokenizer {
         })
     }
 
-    pub fn tokenize(&mut self) {
+    pub fn tokenize(&mut self) -> Result<(), ParseError> {
         while let Some(ch) = self.get_char_type(0) {
             match ch {
                 CharacterType::Whitespace => self.position += 1,
                 CharacterType::Alphabetic => self.get_identifier(),
-                CharacterType::Numeric => self.get_literal(),
+                CharacterType::Numeric => self.get_literal()?,
                 CharacterType::NewLine => {
                     self.position += 1;
                     self.line += 1;
@@ -176,7 +197,22 @@ impl Tokenizer {
                         ')' => self.add_token(TokenType::CloseParentheses),
                         '{' => self.add_token(TokenType::OpenBrace),
                         '}' => self.add_token(TokenType::CloseBrace),
+                        '[' => self.add_token(TokenType::OpenBracket),
+                        ']' => self.add_token(TokenType::CloseBracket),
                         ';' => self.add_token(TokenType::Semicolon),
+                        ',' => self.add_token(TokenType::Comma),
+                        '.' if self.ptr[self.position..].starts_with(&['.', '.']) => {
+                            self.position += 2;
+                            self.add_token(TokenType::Ellipsis)
+                        }
+                        // `.5` is a floating constant
+                        '.' if self.ptr.get(self.position).is_some_and(char::is_ascii_digit) => {
+                            self.position -= 1;
+                            self.get_literal()?
+                        }
+                        '.' => self.add_token(TokenType::Dot),
+                        '\'' => self.get_character()?,
+                        '"' => self.get_string()?,
                         '~' => self.add_token(TokenType::Bitwise),
                         '!' => {
                             if let Some(CharacterType::NonAlphabetic(_ch @ '=')) =
@@ -199,6 +235,11 @@ impl Tokenizer {
                             {
                                 self.position += 1;
                                 self.add_token(TokenType::Decrement)
+                            } else if let Some(CharacterType::NonAlphabetic(_ch @ '>')) =
+                                self.get_char_type(0)
+                            {
+                                self.position += 1;
+                                self.add_token(TokenType::Arrow)
                             } else {
                                 self.add_token(TokenType::Minus)
                             }
@@ -248,7 +289,14 @@ impl Tokenizer {
                                 self.get_char_type(0)
                             {
                                 self.position += 1;
-                                self.add_token(TokenType::BitwiseShiftLeft)
+                                if let Some(CharacterType::NonAlphabetic(_ch @ '=')) =
+                                    self.get_char_type(0)
+                                {
+                                    self.position += 1;
+                                    self.add_token(TokenType::AssignBitwiseLeft)
+                                } else {
+                                    self.add_token(TokenType::BitwiseShiftLeft)
+                                }
                             } else {
                                 self.add_token(TokenType::LessThan)
                             }
@@ -263,7 +311,14 @@ impl Tokenizer {
                                 self.get_char_type(0)
                             {
                                 self.position += 1;
-                                self.add_token(TokenType::BitwiseShiftRight)
+                                if let Some(CharacterType::NonAlphabetic(_ch @ '=')) =
+                                    self.get_char_type(0)
+                                {
+                                    self.position += 1;
+                                    self.add_token(TokenType::AssignBitwiseRight)
+                                } else {
+                                    self.add_token(TokenType::BitwiseShiftRight)
+                                }
                             } else {
                                 self.add_token(TokenType::GreaterThan)
                             }
@@ -274,6 +329,11 @@ impl Tokenizer {
                             {
                                 self.position += 1;
                                 self.add_token(TokenType::LogicalAnd)
+                            } else if let Some(CharacterType::NonAlphabetic(_ch @ '=')) =
+                                self.get_char_type(0)
+                            {
+                                self.position += 1;
+                                self.add_token(TokenType::AssignAND)
                             } else {
                                 self.add_token(TokenType::BitwiseAND)
                             }
@@ -284,6 +344,11 @@ impl Tokenizer {
                             {
                                 self.position += 1;
                                 self.add_token(TokenType::LogicalOr)
+                            } else if let Some(CharacterType::NonAlphabetic(_ch @ '=')) =
+                                self.get_char_type(0)
+                            {
+                                self.position += 1;
+                                self.add_token(TokenType::AssignOR)
                             } else {
                                 self.add_token(TokenType::BitwiseOR)
                             }
@@ -298,8 +363,26 @@ impl Tokenizer {
                                 self.add_token(TokenType::Assignement);
                             }
                         }
-                        '^' => self.add_token(TokenType::BitwiseXOR),
-                        '%' => self.add_token(TokenType::Modulus),
+                        '^' => {
+                            if let Some(CharacterType::NonAlphabetic(_ch @ '=')) =
+                                self.get_char_type(0)
+                            {
+                                self.position += 1;
+                                self.add_token(TokenType::AssignXOR)
+                            } else {
+                                self.add_token(TokenType::BitwiseXOR)
+                            }
+                        }
+                        '%' => {
+                            if let Some(CharacterType::NonAlphabetic(_ch @ '=')) =
+                                self.get_char_type(0)
+                            {
+                                self.position += 1;
+                                self.add_token(TokenType::AssignModulus)
+                            } else {
+                                self.add_token(TokenType::Modulus)
+                            }
+                        }
                         '?' => self.add_token(TokenType::QuestionMark),
                         ':' => self.add_token(TokenType::Colon),
 
@@ -308,6 +391,7 @@ impl Tokenizer {
                 }
             }
         }
+        Ok(())
     }
 
     fn get_identifier(&mut self) {
@@ -324,6 +408,27 @@ impl Tokenizer {
             .collect();
         match value.as_str() {
             "int" => self.add_token(TokenType::Keyword(Keyword::Int)),
+            "char" => self.add_token(TokenType::Keyword(Keyword::Char)),
+            "float" => self.add_token(TokenType::Keyword(Keyword::Float)),
+            "double" => self.add_token(TokenType::Keyword(Keyword::Double)),
+            "void" => self.add_token(TokenType::Keyword(Keyword::Void)),
+            "short" => self.add_token(TokenType::Keyword(Keyword::Short)),
+            "long" => self.add_token(TokenType::Keyword(Keyword::Long)),
+            "signed" => self.add_token(TokenType::Keyword(Keyword::Signed)),
+            "unsigned" => self.add_token(TokenType::Keyword(Keyword::Unsigned)),
+            "struct" => self.add_token(TokenType::Keyword(Keyword::Struct)),
+            "union" => self.add_token(TokenType::Keyword(Keyword::Union)),
+            "enum" => self.add_token(TokenType::Keyword(Keyword::Enum)),
+            "typedef" => self.add_token(TokenType::Keyword(Keyword::Typedef)),
+            "static" => self.add_token(TokenType::Keyword(Keyword::Static)),
+            "extern" => self.add_token(TokenType::Keyword(Keyword::Extern)),
+            "const" => self.add_token(TokenType::Keyword(Keyword::Const)),
+            "volatile" => self.add_token(TokenType::Keyword(Keyword::Volatile)),
+            "switch" => self.add_token(TokenType::Keyword(Keyword::Switch)),
+            "case" => self.add_token(TokenType::Keyword(Keyword::Case)),
+            "default" => self.add_token(TokenType::Keyword(Keyword::Default)),
+            "goto" => self.add_token(TokenType::Keyword(Keyword::Goto)),
+            "sizeof" => self.add_token(TokenType::Keyword(Keyword::Sizeof)),
             "return" => self.add_token(TokenType::Keyword(Keyword::Return)),
             "if" => self.add_token(TokenType::Keyword(Keyword::If)),
             "else" => self.add_token(TokenType::Keyword(Keyword::Else)),
@@ -337,21 +442,247 @@ impl Tokenizer {
         self.position += len;
     }
 
-    fn get_literal(&mut self) {
-        let mut len = 1;
-        while let Some(c) = self.ptr.get(self.position + len) {
-            if c.is_ascii_digit() {
-                len += 1;
-                continue;
+    /// Character literal, the opening quote being already consumed.
+    fn get_character(&mut self) -> Result<(), ParseError> {
+        let value = match self.ptr.get(self.position) {
+            Some('\\') => {
+                self.position += 1;
+                self.get_escape_sequence()?
+            }
+            Some(&c) if c != '\'' && c != '\n' => {
+                self.position += 1;
+                c as u8
+            }
+            Some('\'') => return Err(self.error("empty character literal")),
+            _ => return Err(self.error("unterminated character literal")),
+        };
+        if self.ptr.get(self.position) != Some(&'\'') {
+            return Err(self.error("unterminated character literal"));
+        }
+        self.position += 1;
+        self.add_token(TokenType::Literal(Value::Char(value)));
+        Ok(())
+    }
+
+    /// String literal, the opening quote being already consumed. Adjacent
+    /// literals are concatenated into a single token.
+    fn get_string(&mut self) -> Result<(), ParseError> {
+        let mut value = vec![];
+        loop {
+            match self.ptr.get(self.position) {
+                Some('"') => break,
+                Some('\\') => {
+                    self.position += 1;
+                    let c = self.get_escape_sequence()?;
+                    value.push(c);
+                }
+                Some(&c) if c != '\n' => {
+                    self.position += 1;
+                    let mut buffer = [0; 4];
+                    value.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                }
+                _ => return Err(self.error("unterminated string literal")),
             }
-            break;
         }
+        self.position += 1;
+        if let Some(Token {
+            token: TokenType::Literal(Value::String(previous)),
+            ..
+        }) = self.tokens.last_mut()
+        {
+            previous.append(&mut value);
+        } else {
+            self.add_token(TokenType::Literal(Value::String(value)));
+        }
+        Ok(())
+    }
+
+    /// Escape sequence following a backslash: simple escapes, up to three
+    /// octal digits or `x` followed by hexadecimal digits.
+    fn get_escape_sequence(&mut self) -> Result<u8, ParseError> {
+        let c = match self.ptr.get(self.position) {
+            Some(&c) if c != '\n' => c,
+            _ => return Err(self.error("unterminated escape sequence")),
+        };
+        self.position += 1;
+        Ok(match c {
+            'n' => b'\n',
+            't' => b'\t',
+            'r' => b'\r',
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'v' => 0x0b,
+            '\\' | '\'' | '"' | '?' => c as u8,
+            '0'..='7' => {
+                let mut value = c.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match self.ptr.get(self.position).and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            self.position += 1;
+                        }
+                        None => break,
+                    }
+                }
+                value as u8
+            }
+            'x' => {
+                let mut value: u32 = 0;
+                let start = self.position;
+                while let Some(digit) = self.ptr.get(self.position).and_then(|c| c.to_digit(16)) {
+                    value = value.wrapping_mul(16).wrapping_add(digit);
+                    self.position += 1;
+                }
+                if start == self.position {
+                    return Err(self.error("\\x used with no following hex digits"));
+                }
+                value as u8
+            }
+            c => return Err(self.error(&format!("unknown escape sequence `\\{}`", c))),
+        })
+    }
+
+    /// Error at the current position.
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(message.to_string(), self.position, self.line)
+    }
+
+    /// Integer or floating constant. A floating constant is decimal, has a
+    /// fraction or an exponent, and is a float with the `f` suffix.
+    fn get_literal(&mut self) -> Result<(), ParseError> {
+        let radix = match (self.ptr.get(self.position), self.ptr.get(self.position + 1)) {
+            (Some('0'), Some('x' | 'X')) => 16,
+            (Some('0'), Some('b' | 'B')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            return self.get_integer(radix, 2);
+        }
+
+        let mut len = self.digits(0);
+        let mut floating = false;
+        if self.ptr.get(self.position + len) == Some(&'.') {
+            floating = true;
+            len = self.digits(len + 1);
+        }
+        if let Some('e' | 'E') = self.ptr.get(self.position + len) {
+            let sign = matches!(self.ptr.get(self.position + len + 1), Some('+' | '-')) as usize;
+            if self.digits(len + 1 + sign) > len + 1 + sign {
+                floating = true;
+                len = self.digits(len + 1 + sign);
+            }
+        }
+        if !floating {
+            // A leading 0 starts an octal constant
+            return match len {
+                1 => self.get_integer(10, 0),
+                _ if self.ptr[self.position] == '0' => self.get_integer(8, 1),
+                _ => self.get_integer(10, 0),
+            };
+        }
+
         let value: String = self.ptr[self.position..self.position + len]
             .iter()
             .collect();
-        self.add_token(TokenType::Literal(Value::Int(
-            value.parse().expect("Error parsing literal value"),
-        )));
+        if let Some('f' | 'F') = self.ptr.get(self.position + len) {
+            len += 1;
+            self.add_token(TokenType::Literal(Value::Float(
+                value.parse().expect("Error parsing literal value"),
+            )));
+        } else {
+            self.add_token(TokenType::Literal(Value::Double(
+                value.parse().expect("Error parsing literal value"),
+            )));
+        }
+        self.position += len;
+        Ok(())
+    }
+
+    /// Integer constant whose digits start after a prefix of `len`. It gets
+    /// the first type of its list that holds its value: `u` restricts the
+    /// list to the unsigned types, `l` or `ll` to the longs, and a decimal
+    /// constant without `u` is never unsigned.
+    fn get_integer(&mut self, radix: u32, mut len: usize) -> Result<(), ParseError> {
+        let start = len;
+        while self
+            .ptr
+            .get(self.position + len)
+            .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_')
+        {
+            len += 1;
+        }
+        let text: String = self.ptr[self.position..self.position + len]
+            .iter()
+            .collect();
+        let error = |message: String| ParseError::new(message, self.position, self.line);
+
+        let digits = self.ptr[self.position + start..self.position + len]
+            .iter()
+            .take_while(|c| c.is_digit(radix) || (radix != 16 && c.is_ascii_digit()))
+            .count();
+        let suffix: String = self.ptr[self.position + start + digits..self.position + len]
+            .iter()
+            .collect();
+        if let Some(digit) = self.ptr[self.position + start..self.position + start + digits]
+            .iter()
+            .find(|c| !c.is_digit(radix))
+        {
+            return Err(error(format!(
+                "invalid digit `{}` in constant `{}`",
+                digit, text
+            )));
+        }
+        if digits == 0 {
+            return Err(error(format!("invalid constant `{}`", text)));
+        }
+        let unsigned = suffix.contains(['u', 'U']);
+        let long = suffix
+            .strip_prefix(['u', 'U'])
+            .or_else(|| suffix.strip_suffix(['u', 'U']))
+            .unwrap_or(&suffix);
+        let long = match long {
+            "" => false,
+            "l" | "L" | "ll" | "LL" => true,
+            _ => {
+                return Err(error(format!(
+                    "invalid suffix `{}` on integer constant",
+                    suffix
+                )))
+            }
+        };
+
+        let digits: String = self.ptr[self.position + start..self.position + start + digits]
+            .iter()
+            .collect();
+        let too_large = || error(format!("integer constant `{}` is too large", text));
+        let value = u64::from_str_radix(&digits, radix).map_err(|_| too_large())?;
+        let decimal = radix == 10;
+        let value = if !long && !unsigned && value <= i32::MAX as u64 {
+            Value::Int(value as i32)
+        } else if !long && (unsigned || !decimal) && value <= u32::MAX as u64 {
+            Value::UInt(value as u32)
+        } else if !unsigned && value <= i64::MAX as u64 {
+            Value::Long(value as i64)
+        } else if unsigned || !decimal {
+            Value::ULong(value)
+        } else {
+            return Err(too_large());
+        };
+        self.add_token(TokenType::Literal(value));
         self.position += len;
+        Ok(())
+    }
+
+    /// Length up to the end of the digits starting at `len`.
+    fn digits(&self, mut len: usize) -> usize {
+        while self
+            .ptr
+            .get(self.position + len)
+            .is_some_and(char::is_ascii_digit)
+        {
+            len += 1;
+        }
+        len
     }
 }
@@ -0,0 +1,723 @@
+//! Programs compiled by the compiler, assembled by gcc and run.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Compiles `source` as `name.c` with the compiler binary.
+fn compile(name: &str, source: &str) -> (PathBuf, Output) {
+    let directory = std::env::temp_dir().join(format!("compiler-tests-{}", std::process::id()));
+    fs::create_dir_all(&directory).expect("temporary directory");
+    let file = directory.join(format!("{}.c", name));
+    fs::write(&file, source).expect("source file");
+    let output = Command::new(env!("CARGO_BIN_EXE_compiler"))
+        .arg(&file)
+        .output()
+        .expect("compiler");
+    (directory.join(name), output)
+}
+
+/// Compiles and runs `source`, returning its exit code.
+fn run(name: &str, source: &str) -> i32 {
+    let (executable, output) = compile(name, source);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let errors: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("[ERROR]") || line.starts_with("Error ->"))
+        .collect();
+    assert!(
+        output.status.success() && errors.is_empty(),
+        "{:?} {}",
+        errors,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let status = Command::new(executable).status().expect("executable");
+    status.code().expect("exit code")
+}
+
+/// Compiles `source`, which must be rejected, and returns the diagnostic.
+fn error(name: &str, source: &str) -> String {
+    let (_, output) = compile(name, source);
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.starts_with("[ERROR]"))
+        .unwrap_or_else(|| panic!("`{}` is accepted", name))
+        .to_string()
+}
+
+#[test]
+fn variadic_functions_take_promoted_extra_arguments() {
+    let source = r#"
+int snprintf(char *buffer, unsigned long size, char *format, ...);
+int strcmp(char *a, char *b);
+int main() {
+    char buffer[128];
+    char c = 'x';
+    short s = -3;
+    float f = 1.5;
+    unsigned char u = 200;
+    snprintf(buffer, sizeof buffer, "%d %c %d %.2f %.1f %ld %s %d", 42, c, s, f, 2.75,
+             1234567890123l, "end", u);
+    if (strcmp(buffer, "42 x -3 1.50 2.8 1234567890123 end 200") != 0) return 1;
+    char *format = "%.0f %.0f %.0f %.0f %.0f %.0f %.0f %.0f %.0f %d %d %d %d";
+    int length = snprintf(buffer, sizeof buffer, format, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0,
+                          8.0, f * 6, 10, 11, 12, 13);
+    if (strcmp(buffer, "1 2 3 4 5 6 7 8 9 10 11 12 13") != 0) return 2;
+    if (length != 29) return 3;
+    return 0;
+}
+"#;
+    assert_eq!(run("variadic", source), 0);
+
+    let cases = [
+        ("int f(...);", "a named parameter is required before `...`"),
+        ("int f(int a, ...); int g() { return f(); }", "expects at least 1 argument(s)"),
+        ("void v(); int f(int a, ...); int g() { return f(1, v()); }", "uses a void value"),
+        ("int f(int a, ...); int f(int a);", "conflicting types for `f`"),
+    ];
+    for (index, (declarations, message)) in cases.iter().enumerate() {
+        let source = format!("{}\nint main() {{ return 0; }}\n", declarations);
+        let error = error(&format!("variadic{}", index), &source);
+        assert!(error.contains(message), "{}: {}", declarations, error);
+    }
+}
+
+#[test]
+fn register_names_are_not_symbols() {
+    let cases = [
+        "int gs;",
+        "static int rax = 1;",
+        "int xmm3(void) { return 0; }",
+        "int f(void) { extern int rip; return rip; }",
+    ];
+    for (index, declarations) in cases.iter().enumerate() {
+        let source = format!("{}\nint main() {{ return 0; }}\n", declarations);
+        let error = error(&format!("registers{}", index), &source);
+        assert!(error.contains("is reserved by the assembler"), "{}: {}", declarations, error);
+    }
+
+    let source = r#"
+int r16 = 2;
+int xmm32 = 3;
+int main() {
+    int gs = 1;
+    static int fs = 4;
+    return gs + r16 + xmm32 + fs;
+}
+"#;
+    assert_eq!(run("register_like_names", source), 10);
+}
+
+#[test]
+fn compound_assignment_evaluates_the_lvalue_once() {
+    let source = r#"
+int calls;
+int idx() { calls++; return 1; }
+int main() {
+    int a[4] = {0, 0, 0, 0};
+    int i = 0;
+    a[i++] += 5;
+    if (i != 1 || a[0] != 5) return 1;
+    a[idx()] += 1;
+    if (calls != 1 || a[1] != 1) return 2;
+    a[idx()]++;
+    if (calls != 2 || a[1] != 2) return 3;
+    int old = a[idx()]--;
+    if (calls != 3 || a[1] != 1 || old != 2) return 4;
+    int *p = a;
+    *p++ += 100;
+    if (p != a + 1 || a[0] != 105) return 5;
+    return 0;
+}
+"#;
+    assert_eq!(run("compound_assignment", source), 0);
+}
+
+#[test]
+fn pointer_operands_are_restricted() {
+    let cases = [
+        ("return p * 2;", "invalid operands to binary *"),
+        ("return p / 2;", "invalid operands to binary /"),
+        ("return p + q;", "invalid operands to binary +"),
+        ("return 2 - p;", "invalid operands to binary -"),
+        ("return p == 1;", "invalid operands to binary =="),
+        ("int *r = -p;", "invalid operand to unary -"),
+        ("return ~p;", "invalid operand to unary ~"),
+        ("p *= 2;", "invalid operands to binary *"),
+        ("p /= 2;", "invalid operands to binary /"),
+        ("i += p;", "invalid operands to binary +"),
+        ("p -= q;", "invalid operands to binary -"),
+    ];
+    for (index, (statement, message)) in cases.iter().enumerate() {
+        let source = format!(
+            "int main() {{\nint a[4]; int *p = a; int *q = a; int i = 0;\n{}\nreturn 0;\n}}\n",
+            statement
+        );
+        let error = error(&format!("pointers{}", index), &source);
+        assert!(error.contains(message), "{}: {}", statement, error);
+    }
+
+    let source = r#"
+int main() {
+    int a[4];
+    int *p = a + 1;
+    int *q = 3 + a;
+    p += 1;
+    p--;
+    if (q - p != 2 || p - 1 != a) return 1;
+    if (p == 0 || !(p < q) || !p || (p && 0)) return 2;
+    return 0;
+}
+"#;
+    assert_eq!(run("pointer_arithmetic", source), 0);
+}
+
+#[test]
+fn arrays_are_not_assigned() {
+    let cases = ["a = 0;", "a++;", "--a;", "a += 1;", "s.m = a;"];
+    for (index, statement) in cases.iter().enumerate() {
+        let source = format!(
+            "struct S {{ int m[3]; }} s;\nint main() {{\nint a[3];\n{}\nreturn 0;\n}}\n",
+            statement
+        );
+        let error = error(&format!("arrays{}", index), &source);
+        assert!(error.contains("assignment to expression with array type"), "{}", error);
+    }
+}
+
+#[test]
+fn user_labels_do_not_collide_with_generated_labels() {
+    let source = r#"
+int case12;
+int main() {
+    int i;
+    int s = 0;
+    s = s || 1;
+    goto loop1;
+loop1:
+    switch (s) {}
+    for (i = 0; i < 2; i++) s++;
+    for (i = 0; i < 2; i++) s++;
+    for (i = 0; i < 2; i++) s++;
+    for (i = 0; i < 2; i++) s++;
+    goto _case12;
+_case12:
+    case12 = 1;
+    return s + case12;
+}
+"#;
+    assert_eq!(run("labels", source), 10);
+}
+
+#[test]
+fn bitwise_compound_assignment_evaluates_the_lvalue_once() {
+    let source = r#"
+int calls;
+int idx() { calls++; return 2; }
+int main() {
+    int a[3] = {0, 0, 12};
+    a[idx()] <<= 2;
+    if (a[2] != 48) return 1;
+    a[idx()] >>= 1;
+    if (a[2] != 24) return 2;
+    a[idx()] &= 28;
+    if (a[2] != 24) return 3;
+    a[idx()] |= 3;
+    if (a[2] != 27) return 4;
+    a[idx()] ^= 1;
+    if (a[2] != 26) return 5;
+    a[idx()] %= 7;
+    if (a[2] != 5) return 6;
+    if (calls != 6) return 7;
+    return 0;
+}
+"#;
+    assert_eq!(run("bitwise_compound_assignment", source), 0);
+}
+
+#[test]
+fn malformed_literals_are_reported() {
+    let cases = [
+        ("char c = '';", "empty character literal"),
+        ("char c = 'a;", "unterminated character literal"),
+        ("char *s = \"abc;", "unterminated string literal"),
+        ("char c = '\\q';", "unknown escape sequence `\\q`"),
+        ("char *s = \"\\x\";", "\\x used with no following hex digits"),
+        ("char c = '\\", "unterminated character literal"),
+        ("int x; /* comment", "unterminated comment"),
+    ];
+    for (index, (declarations, message)) in cases.iter().enumerate() {
+        let source = format!("int main() {{ return 0; }}\n{}\n", declarations);
+        let error = error(&format!("literals{}", index), &source);
+        assert!(error.contains(message), "{}: {}", declarations, error);
+    }
+}
+
+#[test]
+fn sizeof_expressions_are_constants() {
+    let source = r#"
+struct Pair { char c; int *p; };
+int arr[5];
+int n = sizeof(arr) / sizeof(arr[0]);
+struct Pair pair;
+char f() { return 1; }
+int main() {
+    int x;
+    int b[sizeof x];
+    char *p;
+    if (n != 5 || sizeof b != 16) return 1;
+    switch (8) {
+        case sizeof(x): return 2;
+        case sizeof p: break;
+        default: return 3;
+    }
+    if (sizeof pair.c != 1 || sizeof pair != 16 || sizeof *p != 1) return 4;
+    if (sizeof "abc" != 4 || sizeof f() != 1) return 5;
+    if (-sizeof(int) < 0 || sizeof(int) - 5 < 0 || sizeof(sizeof x) != 8) return 6;
+    return 0;
+}
+"#;
+    assert_eq!(run("sizeof_expressions", source), 0);
+
+    let error = error("sizeof_undeclared", "int main() { return sizeof y; }\n");
+    assert!(error.contains("`y` undeclared"), "{}", error);
+}
+
+#[test]
+fn compound_assignment_converts_through_the_common_type() {
+    let source = r#"
+int main() {
+    char c = 100;
+    c += 100;
+    if (c != -56) return 1;
+    double d = 1.5;
+    d *= 3;
+    if (d != 4.5) return 2;
+    int i = 7;
+    i *= 1.5;
+    if (i != 10) return 3;
+    float f = 2;
+    float g = f++;
+    if (g != 2 || f != 3) return 4;
+    long l = 1;
+    l <<= 40;
+    if (l != 1099511627776) return 5;
+    unsigned u = 10;
+    u -= 20;
+    if (u / 2 != 2147483643) return 6;
+    short s = 1000;
+    s *= 100;
+    if (s != -31072) return 7;
+    unsigned m = -1;
+    l += m;
+    if (l >> 32 != 256 || (int)l != -1) return 8;
+    return 0;
+}
+"#;
+    assert_eq!(run("compound_conversions", source), 0);
+}
+
+#[test]
+fn unsigned_long_floating_conversions() {
+    let source = r#"
+int main() {
+    unsigned long max = 18446744073709551615ul;
+    if ((double)max != 18446744073709551616.0) return 1;
+    if ((float)max != 18446744073709551616.0) return 2;
+    unsigned long odd = 9223372036854776833ul;
+    if ((double)odd != 9223372036854777856.0) return 3;
+    if ((double)(max / 4) != 4611686018427387904.0) return 4;
+    double big = 1.5e19;
+    if ((unsigned long)big != 15000000000000000000ul) return 5;
+    float f = 1.5e19;
+    if ((unsigned long)f != 15000000520515485696ul) return 6;
+    if ((unsigned long)2.5 != 2) return 7;
+    unsigned long back = 9223372036854775808.0;
+    if (back != 9223372036854775808ul) return 8;
+    return 0;
+}
+"#;
+    assert_eq!(run("unsigned_long_floating", source), 0);
+}
+
+#[test]
+fn integer_operators_reject_floating_operands() {
+    let cases = [
+        ("d % 2", "%"),
+        ("d << 2", "<<"),
+        ("1 >> d", ">>"),
+        ("d & 1", "&"),
+        ("1 | d", "|"),
+        ("d ^ 1", "^"),
+        ("d %= 2", "%"),
+    ];
+    for (index, (expression, op)) in cases.iter().enumerate() {
+        let source = format!("int main() {{ double d = 1; d = {}; return 0; }}\n", expression);
+        let message = error(&format!("floating_operands{}", index), &source);
+        let expected = format!("invalid operands to binary {} ", op);
+        assert!(message.contains(&expected), "{}: {}", expression, message);
+    }
+
+    let source = "int main() { float f = 1; return ~f; }\n";
+    let message = error("floating_complement", source);
+    assert!(message.contains("invalid operand to unary ~"), "{}", message);
+}
+
+#[test]
+fn constant_folding_matches_the_run_time_arithmetic() {
+    let source = r#"
+#if -1 < 1u
+#error "signed comparison in #if"
+#endif
+int g1 = -1 < 1u;
+unsigned g2 = (0u - 1) / 2;
+unsigned long g3 = (0ul - 1) >> 60;
+int g4 = -7 / 2;
+unsigned g5 = -7u % 4;
+long g6 = 1 ? -1 : 0u;
+double g7 = 0ul - 1;
+enum { E = (0u - 1) / 2 };
+int main() {
+    int one = 1;
+    unsigned u1 = 1;
+    if (g1 != (-one < u1)) return 1;
+    if (g2 != (0u - u1) / 2) return 2;
+    if (g3 != (0ul - one) >> 60) return 3;
+    if (g4 != -7 / (2 * one)) return 4;
+    if (g5 != -7u % (4 * u1)) return 5;
+    if (g6 != 4294967295) return 6;
+    if (g7 != 18446744073709551615.0) return 7;
+    if (E != 2147483647) return 8;
+    switch (u1 * 2147483647) {
+    case (0u - 1) / 2:
+        return 0;
+    }
+    return 9;
+}
+"#;
+    assert_eq!(run("constant_folding", source), 0);
+}
+
+#[test]
+fn case_values_are_converted_to_the_switch_type() {
+    let source = r#"
+int chain(long l) {
+    switch (l) {
+    case 4294967295u:
+        return 1;
+    case -1:
+        return 2;
+    case 1l << 40:
+        return 3;
+    }
+    return 0;
+}
+int table(long l) {
+    switch (l) {
+    case (1l << 40) + 1: return 1;
+    case (1l << 40) + 2: return 2;
+    case (1l << 40) + 3: return 3;
+    case (1l << 40) + 4: return 4;
+    }
+    return 0;
+}
+int main() {
+    unsigned char c = 255;
+    if (chain(4294967295) != 1) return 1;
+    if (chain(-1) != 2) return 2;
+    if (chain(1l << 40) != 3) return 3;
+    if (chain(0) != 0) return 4;
+    if (table((1l << 40) + 3) != 3) return 5;
+    if (table(3) != 0) return 6;
+    switch (c - 256) {
+    case 4294967295u:
+        return 0;
+    }
+    return 7;
+}
+"#;
+    assert_eq!(run("long_switch", source), 0);
+
+    let source = "int main() { switch (1) { case -1: case 4294967295u: return 0; } }\n";
+    let message = error("converted_duplicate_case", source);
+    assert!(message.contains("duplicate case value `-1`"), "{}", message);
+
+    let source = "int main() { double d = 1; switch (d) { case 1: return 0; } }\n";
+    let message = error("floating_switch", source);
+    assert!(message.contains("switch quantity not an integer"), "{}", message);
+}
+
+#[test]
+fn redeclarations_in_a_block_are_rejected() {
+    let cases = [
+        ("int main() { int a; int a; return 0; }", "redeclaration of `a`"),
+        ("int f(int a) { int a = 2; return a; }", "redeclaration of `a`"),
+        ("int main() { static int a; int a; return 0; }", "redeclaration of `a`"),
+        ("int main() { extern int g; extern long g; return 0; }", "conflicting types for `g`"),
+        ("int main() { for (int; ;) return 0; }", "declares no variable"),
+    ];
+    for (index, (source, expected)) in cases.iter().enumerate() {
+        let message = error(&format!("redeclaration{}", index), source);
+        assert!(message.contains(expected), "{}: {}", source, message);
+    }
+
+    let source = r#"
+int g = 3;
+int main() {
+    int a = 1;
+    extern int g;
+    extern int g;
+    {
+        int a = 2;
+        g += a;
+    }
+    for (int a = 0; a < 1; a++) {
+        int a = 4;
+        g += a;
+    }
+    return g + a;
+}
+"#;
+    assert_eq!(run("shadowing_declarations", source), 10);
+}
+
+#[test]
+fn address_constants_initialize_static_pointers() {
+    let source = r#"
+struct Point { int x; int y; };
+int g = 7;
+int arr[4] = {1, 2, 3, 4};
+struct Point point = {5, 6};
+char *names[] = {"ab", "cd", 0};
+char *s = "xyz";
+char *t = "xyz" + 1;
+int *gp = &g;
+int *p = arr;
+int *q = &arr[2];
+int *r = arr + 3;
+int *m = &point.y;
+struct Point *pp = &point;
+int count(void) {
+    static char *word = "hello";
+    static int local = 3;
+    static int *lp = &local;
+    static int *ap = &arr[1];
+    return word[4] + *lp + *ap;
+}
+int main() {
+    if (names[0][1] != 'b' || names[1][0] != 'c' || names[2] != 0) return 1;
+    if (s[2] != 'z' || t[0] != 'y') return 2;
+    if (*gp != 7 || gp != &g) return 3;
+    if (*p != 1 || *q != 3 || *r != 4 || r - p != 3) return 4;
+    if (*m != 6 || pp->x != 5) return 5;
+    if (count() != 'o' + 3 + 2) return 6;
+    return 0;
+}
+"#;
+    assert_eq!(run("address_constants", source), 0);
+}
+
+#[test]
+fn non_constant_addresses_are_rejected() {
+    let cases = [
+        "int *q; int *p = q;",
+        "int f() { int local; static int *p = &local; return 0; }",
+        "int g; int *p = &g + g;",
+    ];
+    for (index, declarations) in cases.iter().enumerate() {
+        let source = format!("{}\nint main() {{ return 0; }}\n", declarations);
+        let error = error(&format!("addresses{}", index), &source);
+        assert!(error.contains("is not a constant"), "{}: {}", declarations, error);
+    }
+}
+
+#[test]
+fn file_scope_declarations_must_agree() {
+    let main = "int main() { return 0; }";
+    let cases = [
+        ("int x = 1; int x = 2;", "redefinition of `x`"),
+        ("extern int x; long x = 1;", "conflicting types for `x`"),
+        ("int x; const int x;", "conflicting types for `x`"),
+        ("int x; static int x;", "static declaration of `x` follows non-static"),
+        ("static int x; int x;", "non-static declaration of `x` follows static"),
+        ("int f(); int f;", "`f` redeclared as a different kind of symbol"),
+    ];
+    for (index, (declarations, message)) in cases.iter().enumerate() {
+        let source = format!("{}\n{}\n", declarations, main);
+        let error = error(&format!("globals{}", index), &source);
+        assert!(error.contains(message), "{}: {}", declarations, error);
+    }
+
+    let source = r#"
+int x;
+int x = 3;
+int x;
+extern int y;
+int y = 4;
+static int z;
+extern int z;
+static int z = 5;
+int main() { return x + y + z; }
+"#;
+    assert_eq!(run("compatible_globals", source), 12);
+}
+
+#[test]
+fn invalid_expressions_are_reported() {
+    let cases = [
+        ("int a; a.b = 1;", "request for member `b` in something not a struct"),
+        ("int a; return *a;", "invalid type argument of unary `*`"),
+        ("x = 1;", "`x` undeclared"),
+        ("struct S { int x; } s; if (s.y) return 1;", "`struct S` has no member named `y`"),
+        ("int a; while (*a) {}", "invalid type argument of unary `*`"),
+        ("struct T *p; p->x;", "`struct T` is an incomplete type"),
+        ("return f();", "implicit declaration of function `f`"),
+    ];
+    for (index, (statements, message)) in cases.iter().enumerate() {
+        let source = format!("int main() {{\n{}\nreturn 0;\n}}\n", statements);
+        let error = error(&format!("expressions{}", index), &source);
+        assert!(error.contains(message), "{}: {}", statements, error);
+        // Lines are counted from 0
+        assert!(error.contains(" : L:1:"), "{}: {}", statements, error);
+    }
+}
+
+#[test]
+fn implicit_conversions_must_be_compatible() {
+    let cases = [
+        "struct s v; v = 5;",
+        "struct s v; struct t w; v = w;",
+        "struct s v; int x = v;",
+        "int *p; double *d; d = p;",
+        "int *p; double d = p;",
+        "struct s v; f(v);",
+        "int *p = 1;",
+    ];
+    for (index, statements) in cases.iter().enumerate() {
+        let source = format!(
+            "struct s {{ int a; }};\nstruct t {{ int a; }};\nint f(int a) {{ return a; }}\n\
+             int main() {{ {} return 0; }}\n",
+            statements
+        );
+        let message = error(&format!("conversions{}", index), &source);
+        assert!(message.contains("incompatible types in"), "{}: {}", statements, message);
+    }
+
+    let source = r#"
+struct s { int a; };
+double *g = 0;
+int main() {
+    int x = 2;
+    int *p = &x;
+    void *q = p;
+    const int *c = q;
+    char s[] = "ab";
+    double d = 'a';
+    struct s v = {3}, w;
+    w = v;
+    p = 0;
+    return *c + w.a + (d == 97.0) + sizeof s;
+}
+"#;
+    assert_eq!(run("compatible_conversions", source), 9);
+}
+
+#[test]
+fn qualified_objects_are_read_only() {
+    let cases = [
+        ("const int x = 1; x = 2;", "assignment of read-only variable `x`"),
+        ("const int x = 1; x++;", "assignment of read-only variable `x`"),
+        ("int y; const int *p = &y; *p = 2;", "assignment of read-only location"),
+        ("struct s { const int m; } v; v.m += 1;", "assignment of read-only location"),
+        ("const int y = 1; int *p = &y;", "discards `const` qualifier"),
+        ("volatile int y; int *p = &y;", "discards `volatile` qualifier"),
+    ];
+    for (index, (statements, message)) in cases.iter().enumerate() {
+        let source = format!("int main() {{ {} return 0; }}\n", statements);
+        let error = error(&format!("qualifiers{}", index), &source);
+        assert!(error.contains(message), "{}: {}", statements, error);
+    }
+
+    let source = r#"
+typedef const int cint;
+int main() {
+    cint a = 3;
+    int b = 4;
+    int * const p = &b;
+    const volatile int *q = p;
+    *p += a;
+    char const *s = "ok";
+    return *q + s[1] - 'k';
+}
+"#;
+    assert_eq!(run("qualified_objects", source), 7);
+}
+
+#[test]
+fn void_values_are_not_used() {
+    let cases = [
+        "int x = -v();",
+        "int x = v() + 1;",
+        "int x = 0; x += v();",
+        "int x = 1 ? v() : 2;",
+        "int x = v() ? 1 : 2;",
+        "if (v()) return 1;",
+        "while (v()) {}",
+        "for (; v(); ) {}",
+        "do {} while (v());",
+    ];
+    for (index, statements) in cases.iter().enumerate() {
+        let source = format!("void v(void) {{}}\nint main() {{ {} return 0; }}\n", statements);
+        let message = error(&format!("void_values{}", index), &source);
+        let expected = "void value not ignored as it ought to be";
+        assert!(message.contains(expected), "{}: {}", statements, message);
+    }
+
+    let source = "struct s { int a; };\nint main() { struct s a; if (a) return 1; return 0; }\n";
+    let message = error("struct_condition", source);
+    assert!(message.contains("where scalar is required"), "{}", message);
+
+    let source = r#"
+int count;
+void v(void) { count++; }
+int main() {
+    1 ? v() : v();
+    0 ? v() : v();
+    v(), v();
+    return count;
+}
+"#;
+    assert_eq!(run("void_expressions", source), 4);
+}
+
+#[test]
+fn void_functions_return_without_a_value() {
+    let source = r#"
+int total;
+void add(int n) {
+    if (n < 0) return;
+    total += n;
+}
+int main(void) {
+    char buffer[4] = {1, 2, 3, 4};
+    void *p = buffer;
+    add(5);
+    add(-1);
+    add(2);
+    (void)total;
+    return total + *(char *)(p + 2);
+}
+"#;
+    assert_eq!(run("void_functions", source), 10);
+
+    let cases = [
+        ("void f(void) { return 1; }", "`return` with a value, in function returning void"),
+        ("int f(void) { return; }", "`return` with no value, in function returning non-void"),
+        ("int f(void x) { return 0; }", "parameter `x` has void type"),
+    ];
+    for (index, (declarations, message)) in cases.iter().enumerate() {
+        let source = format!("{}\nint main() {{ return 0; }}\n", declarations);
+        let error = error(&format!("void_functions{}", index), &source);
+        assert!(error.contains(message), "{}: {}", declarations, error);
+    }
+}